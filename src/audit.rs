@@ -0,0 +1,390 @@
+use crate::config::Dependency;
+use crate::error::GrindError;
+use crate::lock;
+use crate::util;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+/* -------------------------------------------------------------------------------------------------
+Audits the grind.lock against a local vulnerability database in the OSV format
+(https://ossf.github.io/osv-schema/), no network access is needed. The database is simply a folder
+of OSV `.json` files (e.g an export of the Maven ecosystem from osv.dev) synced into
+`~/.grind/advisories`, or any file/folder passed via `--db`.
+------------------------------------------------------------------------------------------------- */
+
+const DEFAULT_DB: &str = "~/.grind/advisories";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Severity {
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AdvisoryFile {
+    Many(Vec<Advisory>),
+    One(Box<Advisory>),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Advisory {
+    id: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    summary: Option<String>,
+    #[serde(default)]
+    affected: Vec<Affected>,
+    #[serde(default)]
+    database_specific: Option<DatabaseSpecific>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Affected {
+    package: Package,
+    #[serde(default)]
+    ranges: Vec<Range>,
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Package {
+    ecosystem: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Range {
+    #[serde(rename = "type")]
+    r#type: String,
+    #[serde(default)]
+    events: Vec<Event>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Event {
+    introduced: Option<String>,
+    fixed: Option<String>,
+    last_affected: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct DatabaseSpecific {
+    severity: Option<String>,
+}
+
+struct Finding {
+    dep: Dependency,
+    advisory: Advisory,
+    severity: Option<Severity>,
+    affected: String,
+    fixed: Option<String>,
+}
+
+pub fn execute_audit(db: Option<PathBuf>, fail_on: Severity) -> Result<(), GrindError> {
+    let locked = match lock::get_lock_file() {
+        Ok(locked) => locked,
+        Err(e) => {
+            return Err(GrindError::Resolution(format!(
+                "❌ Unable to read grind.lock, try `grind install` first: {}",
+                e
+            )));
+        }
+    };
+
+    let db = db.unwrap_or(util::expand_tilde(DEFAULT_DB).unwrap_or(PathBuf::from(DEFAULT_DB)));
+    let advisories = match self::load_advisories(&db) {
+        Ok(advisories) => advisories,
+        Err(e) => {
+            return Err(GrindError::Other(format!(
+                "❌ Unable to load advisories from {}: {}",
+                db.display(),
+                e
+            )));
+        }
+    };
+
+    println!(
+        "🔍 auditing {} locked dependencies against {} advisories in {}",
+        locked.lockedDeps.len(),
+        advisories.len(),
+        db.display()
+    );
+
+    let mut findings = Vec::new();
+    for dep in &locked.lockedDeps {
+        for advisory in &advisories {
+            if let Some((affected, fixed)) = self::is_affected(advisory, dep) {
+                findings.push(Finding {
+                    dep: dep.clone(),
+                    advisory: advisory.clone(),
+                    severity: self::get_severity(advisory),
+                    affected,
+                    fixed,
+                });
+            }
+        }
+    }
+
+    if findings.is_empty() {
+        println!("✅ No known vulnerabilities found.");
+        return Ok(());
+    }
+
+    findings.sort_by_key(|f| Reverse(f.severity));
+
+    println!();
+    for finding in &findings {
+        let severity = match finding.severity {
+            Some(s) => format!("{:?}", s).to_uppercase(),
+            None => "UNKNOWN".to_string(),
+        };
+        let mut ids = vec![finding.advisory.id.clone()];
+        ids.extend(finding.advisory.aliases.clone());
+
+        println!(
+            "🚨 [{}] {}:{}:{} | {}",
+            severity,
+            finding.dep.groupId,
+            finding.dep.artifactId,
+            finding.dep.version,
+            ids.join(", ")
+        );
+        if let Some(summary) = &finding.advisory.summary {
+            println!("   {}", summary);
+        }
+        println!("   ➜ affected versions: {}", finding.affected);
+        match &finding.fixed {
+            Some(fixed) => println!("   ➜ fixed in v{}", fixed),
+            None => println!("   ➜ no fixed version available"),
+        }
+        println!();
+    }
+
+    // an advisory without a severity can't be proven to be below the threshold, so it always fails
+    let failing = findings
+        .iter()
+        .filter(|f| f.severity.map(|s| s >= fail_on).unwrap_or(true))
+        .count();
+
+    println!(
+        "📄 Summary: {} vulnerabilit(ies) found, {} at or above {:?}",
+        findings.len(),
+        failing,
+        fail_on
+    );
+
+    if failing > 0 {
+        return Err(GrindError::Vulnerabilities("❌ Audit failed.".to_string()));
+    }
+    Ok(())
+}
+
+fn load_advisories(db: &Path) -> Result<Vec<Advisory>, String> {
+    if !db.exists() {
+        return Err("no such file or directory".to_string());
+    }
+
+    let mut advisories = Vec::new();
+
+    for entry in WalkDir::new(db).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        if !path.is_file() || path.extension().map(|e| e != "json").unwrap_or(true) {
+            continue;
+        }
+
+        let raw = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        match serde_json::from_str::<AdvisoryFile>(&raw) {
+            Ok(AdvisoryFile::Many(many)) => advisories.extend(many),
+            Ok(AdvisoryFile::One(one)) => advisories.push(*one),
+            Err(e) => println!("⚠️ skipping {}: {}", path.display(), e),
+        }
+    }
+
+    Ok(advisories)
+}
+
+fn get_severity(advisory: &Advisory) -> Option<Severity> {
+    let severity = advisory.database_specific.as_ref()?.severity.as_deref()?;
+
+    match severity.to_uppercase().as_str() {
+        "LOW" => Some(Severity::Low),
+        "MODERATE" | "MEDIUM" => Some(Severity::Moderate),
+        "HIGH" => Some(Severity::High),
+        "CRITICAL" => Some(Severity::Critical),
+        _ => None,
+    }
+}
+
+fn is_affected(advisory: &Advisory, dep: &Dependency) -> Option<(String, Option<String>)> {
+    /* ---------------------------------------------------------------------------------------------
+    returns `None` when not affected, otherwise the affected versions along with the (optional)
+    version the issue was fixed in
+    --------------------------------------------------------------------------------------------- */
+    let name = format!("{}:{}", dep.groupId, dep.artifactId);
+
+    for affected in &advisory.affected {
+        if !affected.package.ecosystem.eq_ignore_ascii_case("maven")
+            || affected.package.name != name
+        {
+            continue;
+        }
+
+        let listed = affected
+            .versions
+            .iter()
+            .any(|v| util::compare_maven_versions(v, &dep.version) == Ordering::Equal);
+
+        for range in &affected.ranges {
+            if range.r#type == "GIT" {
+                continue;
+            }
+            if self::is_in_range(&range.events, &dep.version) {
+                return Some((
+                    self::describe_range(&range.events),
+                    self::get_fixed(&range.events, &dep.version),
+                ));
+            }
+        }
+
+        if listed {
+            let fixed = affected
+                .ranges
+                .iter()
+                .find_map(|r| self::get_fixed(&r.events, &dep.version));
+            return Some((affected.versions.join(", "), fixed));
+        }
+    }
+    None
+}
+
+fn event_version(event: &Event) -> &str {
+    event
+        .introduced
+        .as_deref()
+        .or(event.fixed.as_deref())
+        .or(event.last_affected.as_deref())
+        .unwrap_or("0")
+}
+
+fn is_in_range(events: &[Event], version: &str) -> bool {
+    // walk the events in version order, see the OSV spec "evaluation" section
+    let mut events = events.to_vec();
+    events.sort_by(|a, b| util::compare_maven_versions(event_version(a), event_version(b)));
+
+    let mut affected = false;
+    for event in &events {
+        if let Some(introduced) = &event.introduced
+            && (introduced == "0"
+                || util::compare_maven_versions(version, introduced) != Ordering::Less)
+        {
+            affected = true;
+        }
+        if let Some(fixed) = &event.fixed
+            && util::compare_maven_versions(version, fixed) != Ordering::Less
+        {
+            affected = false;
+        }
+        if let Some(last) = &event.last_affected
+            && util::compare_maven_versions(version, last) == Ordering::Greater
+        {
+            affected = false;
+        }
+    }
+    affected
+}
+
+fn describe_range(events: &[Event]) -> String {
+    events
+        .iter()
+        .map(|e| match (&e.introduced, &e.fixed, &e.last_affected) {
+            (Some(v), _, _) if v == "0" => ">= 0".to_string(),
+            (Some(v), _, _) => format!(">= {}", v),
+            (_, Some(v), _) => format!("< {}", v),
+            (_, _, Some(v)) => format!("<= {}", v),
+            _ => String::new(),
+        })
+        .filter(|e| !e.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn get_fixed(events: &[Event], version: &str) -> Option<String> {
+    events
+        .iter()
+        .filter_map(|e| e.fixed.clone())
+        .filter(|fixed| util::compare_maven_versions(fixed, version) == Ordering::Greater)
+        .min_by(|a, b| util::compare_maven_versions(a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osv_range_matching() {
+        let raw = r#"{
+            "id": "GHSA-57j2-w4cx-62h2",
+            "aliases": ["CVE-2020-36518"],
+            "summary": "Deeply nested json in jackson-databind",
+            "affected": [{
+                "package": { "ecosystem": "Maven", "name": "com.fasterxml.jackson.core:jackson-databind" },
+                "ranges": [
+                    { "type": "ECOSYSTEM", "events": [{ "introduced": "0" }, { "fixed": "2.12.6.1" }] },
+                    { "type": "ECOSYSTEM", "events": [{ "introduced": "2.13.0" }, { "fixed": "2.13.2.1" }] }
+                ]
+            }],
+            "database_specific": { "severity": "HIGH" }
+        }"#;
+
+        let advisory = match serde_json::from_str::<AdvisoryFile>(raw).unwrap() {
+            AdvisoryFile::One(one) => *one,
+            AdvisoryFile::Many(_) => panic!("expected a single advisory"),
+        };
+        assert_eq!(get_severity(&advisory), Some(Severity::High));
+
+        let dep = |version: &str| Dependency {
+            groupId: "com.fasterxml.jackson.core".to_string(),
+            artifactId: "jackson-databind".to_string(),
+            version: version.to_string(),
+            scope: None,
+            ..Default::default()
+        };
+
+        let cases = vec![
+            (
+                "2.9.10",
+                Some((">= 0, < 2.12.6.1".to_string(), Some("2.12.6.1".to_string()))),
+            ),
+            ("2.12.6.1", None),
+            (
+                "2.13.1",
+                Some((
+                    ">= 2.13.0, < 2.13.2.1".to_string(),
+                    Some("2.13.2.1".to_string()),
+                )),
+            ),
+            ("2.13.2.1", None),
+            ("2.17.2", None),
+        ];
+
+        for (version, expected) in cases {
+            assert_eq!(
+                is_affected(&advisory, &dep(version)),
+                expected,
+                "version {}",
+                version
+            );
+        }
+    }
+}
@@ -1,7 +1,21 @@
 use crate::Grind;
+use crate::RunArgs;
+use crate::config::ProjectType;
+use crate::error::GrindError;
+use crate::incremental;
+use crate::install;
+use crate::java;
+use crate::library;
+use crate::lock;
+use crate::resources;
+use crate::rules;
+use crate::util;
 use crate::util::ls_with_ext;
-use crate::util::shell;
 use std::fs;
+use std::path::Path;
+use std::process::ExitStatus;
+
+const TEST_SOURCES_FILE: &str = "cache/build-test-sources.txt";
 
 #[derive(PartialEq)]
 pub enum BuildTarget {
@@ -10,73 +24,324 @@ pub enum BuildTarget {
     IncludeTest,
 }
 
-pub fn execute_build(grind: &Grind, target: BuildTarget, build_flags: String) {
+pub fn execute_build(grind: &Grind, target: BuildTarget, args: &RunArgs) -> Result<(), GrindError> {
+    let (locked, divergent) = lock::get_lock_file()
+        .map(|l| (l.lockedDeps, l.divergentDeps))
+        // no grind.lock yet, so nothing to converge either
+        .unwrap_or((Vec::new(), Some(Vec::new())));
+
+    if !rules::enforce(grind, &locked, divergent.as_deref()) || !rules::enforce_jars(grind, &locked)
+    {
+        return Err(GrindError::Resolution(
+            "❌ build aborted, project rules failed!".to_string(),
+        ));
+    }
+
+    let build_flags = [
+        self::get_processor_flags(grind)?,
+        self::get_javac_flags(grind, &args.flags),
+    ]
+    .concat();
+    self::check_release(grind);
+
     println!("==> 🔨 compiling project [{}]...", grind.project.artifactId);
     std::fs::create_dir_all(format!("{}/target", grind.project.artifactId)).unwrap();
 
-    shell("rm -rf target/");
-    shell("mkdir target");
+    // compiled tests are always rebuilt, and must never end up in the jar or bundle
+    let _ = fs::remove_dir_all("target/test");
+
+    let compiled = incremental::compile(&build_flags);
 
-    let out = shell(&format!(
-        "javac {} -d target -cp \"libs/*\" $(find src/main/java -name \"*.java\")",
-        &build_flags
-    ));
-    if !out.is_empty() {
-        println!("{}", out);
+    // resources go in before packaging, so the jar gets the filtered ones
+    if let Err(e) = resources::copy_resources(grind, args) {
+        println!("⚠️ Error: unable to copy resources: {}", e);
     }
-    if target == BuildTarget::IncludeJar {
-        println!("==> 🔨 building manifest...");
 
-        let external_jars = ls_with_ext("libs", "jar").unwrap_or_else(|err| {
+    let result = if !compiled {
+        Err(GrindError::Compilation(
+            "❌ compilation failed!".to_string(),
+        ))
+    } else if target == BuildTarget::IncludeJar {
+        match grind.project.r#type.unwrap_or_default() {
+            ProjectType::Application => self::build_jar(grind, args.bin.as_deref()),
+            ProjectType::Library => library::build_library(grind),
+        }
+    } else if target == BuildTarget::IncludeTest {
+        self::compile_tests(grind, &build_flags)
+    } else {
+        Ok(())
+    };
+
+    // not sure why javac seems to create some extra folders, but lets clean them up
+    let _ = fs::remove_dir_all(&grind.project.artifactId);
+
+    result
+}
+
+fn build_jar(grind: &Grind, bin: Option<&str>) -> Result<(), GrindError> {
+    let main_class = self::get_main_class(grind, bin)?;
+
+    println!("==> 🔨 building manifest...");
+
+    let provided = install::get_provided_jars();
+    let external_jars = ls_with_ext("libs", "jar")
+        .unwrap_or_else(|err| {
             println!("⚠️ Error: unable to list external jars: {}", err);
             Vec::new()
-        });
-        let mut manifest = String::new();
+        })
+        .into_iter()
+        .filter(|jar| !provided.contains(jar))
+        .collect::<Vec<_>>();
+    let mut manifest = String::new();
+
+    manifest.push_str(&format!("Main-Class: {}", main_class));
+    manifest.push_str(&self::get_release_attributes(grind));
 
-        manifest.push_str(&format!(
-            "Main-Class: {}.{}",
-            grind.project.groupId, grind.project.artifactId
+    if !external_jars.is_empty() {
+        manifest.push_str(&format!("\nClass-Path: {}", external_jars.join("\n    ")));
+    }
+    manifest.push('\n');
+
+    if fs::write("src/main/resources/manifest.mf", manifest).is_err() {
+        return Err(GrindError::Compilation(
+            "⚠️ Error: unbale to generate the manifest!".to_string(),
         ));
+    }
 
-        if !external_jars.is_empty() {
-            manifest.push_str(&format!("\nClass-Path: {}", external_jars.join("\n    ")));
+    let _ = fs::remove_dir_all("build");
+    fs::create_dir_all("build")?;
+
+    let status = util::exec(
+        java::command("jar")
+            .arg("cfm")
+            .arg(format!("build/{}.jar", grind.project.artifactId))
+            .arg("src/main/resources/manifest.mf")
+            .args(self::get_jar_contents()?),
+    );
+    self::check_status("jar", status)
+}
+
+pub fn get_jar_contents() -> Result<Vec<String>, GrindError> {
+    // everything in target/ except the generated sources, which are .java files and not classes
+    let mut args = Vec::new();
+    for entry in fs::read_dir("target")? {
+        let path = entry?.path();
+        if path != Path::new(incremental::GENERATED_DIR) {
+            args.extend([
+                "-C".to_string(),
+                "target".to_string(),
+                path.file_name().unwrap().to_string_lossy().to_string(),
+            ]);
         }
-        manifest.push('\n');
+    }
+    Ok(args)
+}
 
-        if fs::write("src/main/resources/manifest.mf", manifest).is_ok() {
-            println!("{}", shell("rm -rf build/"));
-            println!("{}", shell("mkdir -p build/"));
+fn get_processor_flags(grind: &Grind) -> Result<Vec<String>, GrindError> {
+    /* ---------------------------------------------------------------------------------------------
+    the `annotationProcessors:` are installed into libs-processors/ by `grind install` and only
+    ever handed to javac, along with the `processorOptions:` e.g
 
-            shell("cp -r src/main/resources/. target/");
+        -processorpath libs-processors/org.mapstruct_mapstruct-processor_1.6.3.jar
+        -s target/generated-sources
+        -Amapstruct.defaultComponentModel=spring
+    --------------------------------------------------------------------------------------------- */
+    if grind
+        .project
+        .annotationProcessors
+        .as_ref()
+        .is_none_or(|p| p.is_empty())
+    {
+        return Ok(Vec::new());
+    }
 
-            let cmd = format!(
-                "jar cfm build/{}.jar src/main/resources/manifest.mf -C target .",
-                grind.project.artifactId
-            );
-            let out = shell(&cmd);
-            if !out.is_empty() {
-                println!("{}", out);
-            }
-        } else {
-            println!("⚠️ Error: unbale to generate the manifest!");
+    let jars = install::get_processor_jars();
+    if jars.is_empty() || jars.iter().any(|jar| !Path::new(jar).exists()) {
+        return Err(GrindError::Resolution(
+            "❌ annotation processors are not installed, run `grind install` first".to_string(),
+        ));
+    }
+
+    let mut flags = vec![
+        "-processorpath".to_string(),
+        jars.join(":"),
+        "-s".to_string(),
+        incremental::GENERATED_DIR.to_string(),
+    ];
+
+    let mut options: Vec<_> = grind
+        .project
+        .processorOptions
+        .iter()
+        .flatten()
+        .map(|(key, value)| format!("-A{}={}", key, value))
+        .collect();
+    options.sort();
+    flags.extend(options);
+
+    Ok(flags)
+}
+
+pub fn get_javac_flags(grind: &Grind, build_flags: &[String]) -> Vec<String> {
+    /* ---------------------------------------------------------------------------------------------
+    the `java:` section of the grind.yml e.g
+
+        java:
+          release: 17
+          encoding: "UTF-8"
+          lint: ["all", "-serial"]
+
+    becomes `--release 17 -encoding UTF-8 -Xlint:all,-serial`, followed by the profile's own flags.
+    javac refuses `--release` alongside `-source`/`-target`, so a profile that sets them wins.
+    --------------------------------------------------------------------------------------------- */
+    let mut flags = Vec::new();
+
+    if let Some(java) = &grind.project.java {
+        let overridden = build_flags.iter().any(|flag| {
+            matches!(
+                flag.as_str(),
+                "--release" | "-source" | "--source" | "-target" | "--target"
+            ) || flag.starts_with("--release=")
+        });
+
+        if let Some(release) = java.release
+            && !overridden
+        {
+            flags.extend(["--release".to_string(), release.to_string()]);
+        }
+        if let Some(encoding) = &java.encoding
+            && !build_flags.iter().any(|flag| flag == "-encoding")
+        {
+            flags.extend(["-encoding".to_string(), encoding.clone()]);
+        }
+        if let Some(lint) = java.lint.as_ref().filter(|lint| !lint.is_empty()) {
+            flags.push(format!("-Xlint:{}", lint.join(",")));
         }
     }
 
-    if target == BuildTarget::IncludeTest {
+    flags.extend(build_flags.iter().cloned());
+    flags
+}
+
+pub fn get_release_attributes(grind: &Grind) -> String {
+    // the same attributes the maven-bundle-plugin writes, one per line so they can be appended
+    match grind.project.java.as_ref().and_then(|java| java.release) {
+        Some(release) => format!(
+            "\nX-Compile-Source-JDK: {0}\nX-Compile-Target-JDK: {0}",
+            release
+        ),
+        None => String::new(),
+    }
+}
+
+fn check_release(grind: &Grind) {
+    let Some(release) = grind.project.java.as_ref().and_then(|java| java.release) else {
+        return;
+    };
+
+    // only a warning, javac has the final say (and a much better error message)
+    if let Ok(version) = java::get_java_version(true)
+        && let Some(major) = rules::get_java_major(&version)
+        && major < release
+    {
         println!(
-            "==> 🔨 compiling tests for [{}]...",
-            grind.project.artifactId
+            "⚠️ java.release is {}, but the active JDK is v{}, install a newer one with `grind java use {}`",
+            release, version, release
         );
-        let out = shell(
-            "javac -d target/test -cp \"target:libs/*\" $(find src/test/java -name \"*.java\")",
-        );
-        if !out.is_empty() {
-            println!("{}", out);
+    }
+}
+
+pub fn get_main_class(grind: &Grind, bin: Option<&str>) -> Result<String, GrindError> {
+    /* ---------------------------------------------------------------------------------------------
+    in order of precedence:
+
+        1. `--bin <name>`, looked up in the `entrypoints:`
+        2. the `mainClass:`
+        3. the compiled class with a `public static void main(String[])`, if there's more than one
+           the `{groupId}.{artifactId}` class wins (which is what `grind new` generates)
+    --------------------------------------------------------------------------------------------- */
+    let project = &grind.project;
+
+    if let Some(bin) = bin {
+        let entrypoints = project.entrypoints.clone().unwrap_or_default();
+        return match entrypoints.get(bin) {
+            Some(class) => Ok(class.clone()),
+            None => {
+                let mut names: Vec<&str> = entrypoints.keys().map(|n| n.as_str()).collect();
+                names.sort();
+                Err(GrindError::Config(format!(
+                    "❌ unknown entrypoint '{}', available entrypoints: [{}]",
+                    bin,
+                    names.join(", ")
+                )))
+            }
+        };
+    }
+
+    if let Some(main_class) = &project.mainClass {
+        return Ok(main_class.clone());
+    }
+
+    // nested classes keep their `$` e.g com.example.App$Cli, which is what `java` expects
+    let mut candidates: Vec<String> = incremental::read_class_index("target")
+        .into_values()
+        .filter(|info| info.has_main)
+        .map(|info| info.name.replace('/', "."))
+        .collect();
+    candidates.sort();
+
+    let conventional = format!("{}.{}", project.groupId, project.artifactId);
+
+    let main_class = match candidates.as_slice() {
+        [] => {
+            return Err(GrindError::Config(
+                "❌ no main class found, add a `public static void main(String[] args)` or set `mainClass:` in the grind.yml".to_string(),
+            ));
         }
+        [only] => only.clone(),
+        _ if candidates.contains(&conventional) => conventional,
+        _ => {
+            return Err(GrindError::Config(format!(
+                "❌ found more than one main class [{}], set `mainClass:` (or `entrypoints:` and use --bin) in the grind.yml",
+                candidates.join(", ")
+            )));
+        }
+    };
+    println!("🔎 detected main class {}", main_class);
+    Ok(main_class)
+}
+
+fn compile_tests(grind: &Grind, build_flags: &[String]) -> Result<(), GrindError> {
+    println!(
+        "==> 🔨 compiling tests for [{}]...",
+        grind.project.artifactId
+    );
+
+    let sources = util::list_files("src/test/java", "java");
+    if sources.is_empty() {
+        println!("ℹ️ no tests found in src/test/java");
+        return Ok(());
     }
 
-    shell("cp -r src/main/resources/. target/");
+    util::write_argfile(TEST_SOURCES_FILE, &sources)
+        .map_err(|e| format!("❌ unable to write the test sources: {}", e))?;
 
-    // not sure why javac seems to create some extra folders, but lets clean them up
-    shell(&format!("rm -rf {}/", grind.project.artifactId));
+    let status = util::exec(
+        java::command("javac")
+            .args(build_flags)
+            .args(["-d", "target/test", "-cp", "target:libs/*:libs-test/*"])
+            .arg(format!("@{}", TEST_SOURCES_FILE)),
+    );
+    self::check_status("javac", status)
+}
+
+pub fn check_status(program: &str, status: Result<ExitStatus, String>) -> Result<(), GrindError> {
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(GrindError::Compilation(format!(
+            "❌ {} failed with: {}",
+            program, status
+        ))),
+        Err(e) => Err(GrindError::Compilation(e)),
+    }
 }
@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -6,39 +7,160 @@ use std::collections::HashMap;
 The main struct defining the `grind` project configuration file. Changing this could potentially be
 a *breaking* change.
 NOTE: using serde(default) on Option<T> allows for missing fields
+NOTE: the `///` doc comments end up as descriptions in the JSON schema (see `grind schema`)
 ------------------------------------------------------------------------------------------------- */
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Grind {
     pub project: Project,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Project {
     #[allow(non_snake_case)]
+    /// the project's namespace e.g com.example
     pub groupId: String,
     #[allow(non_snake_case)]
+    /// the project's name e.g HelloWorld
     pub artifactId: String,
     pub version: String,
     pub name: String,
     pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// `application` (the default) builds a runnable jar, `library` a plain jar along with a .pom
+    pub r#type: Option<ProjectType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// libraries only, also build a -sources.jar
+    pub sourcesJar: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// pins the JDK e.g 21, installed into ~/.grind/jdks/v21 when missing (or use a `.grind-jdk`)
+    pub jdk: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// the Java release to compile for, the source encoding and lint options
+    pub java: Option<JavaOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// the fully qualified main class e.g com.example.Main, detected from the compiled classes when
+    /// left out
+    pub mainClass: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// named main classes, picked with `--bin <name>` e.g worker: "com.example.Worker"
+    pub entrypoints: Option<HashMap<String, String>>,
+    /// managed with `grind add` and `grind remove`
     pub dependencies: Vec<Dependency>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// annotation processors e.g lombok or mapstruct-processor, resolved like the dependencies but
+    /// only ever passed to javac via `-processorpath`
+    pub annotationProcessors: Option<Vec<Dependency>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// options for the annotation processors, passed to javac as `-Akey=value`
+    pub processorOptions: Option<HashMap<String, String>>,
+    /// custom shell commands, run with `grind task <name>`
     pub tasks: HashMap<String, String>,
     #[serde(default)]
+    /// compiler flags, environment variables and resource folders, picked with `--profile`
     pub profiles: Option<HashMap<String, Profile>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// the profile used by build, run and bundle when no `--profile` is given
+    pub defaultProfile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// globs (relative to src/main/resources) of the resources to filter e.g ["**/*.properties"],
+    /// the rest are copied untouched
+    pub filterResources: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// custom values substituted into the filtered resources as `${name}`
+    pub properties: Option<HashMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// forced versions, these win over any version found in the dependency graph
+    pub constraints: Option<Vec<Dependency>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// verify the PGP signature of every dependency on install
+    pub verifySignatures: Option<SignatureMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// groupId prefix => trusted key fingerprints (or long key ids)
+    pub trustedKeys: Option<HashMap<String, Vec<String>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// the GnuPG keyring, defaults to ~/.grind/keyring.gpg
+    pub keyring: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// allowed and denied licenses, checked on install
+    pub licensePolicy: Option<LicensePolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// enforcer style rules, checked on install and build
+    pub rules: Option<Rules>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// also download the -sources.jar of every dependency into libs-sources/
+    pub downloadSources: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// also download the -javadoc.jar of every dependency into libs-sources/
+    pub downloadJavadoc: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// a local JSON index file used by `grind search` instead of Maven Central
+    pub searchIndex: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectType {
+    #[default]
+    Application,
+    Library,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct JavaOptions {
+    /// compile for this Java release e.g 17, passed to javac as `--release`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<u32>,
+    /// the encoding of the source files e.g UTF-8, passed to javac as `-encoding`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// javac lint options e.g ["all", "-serial"], passed to javac as `-Xlint:all,-serial`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lint: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct LicensePolicy {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureMode {
+    Warn,
+    Strict,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Profile {
     #[serde(default)]
     pub flags: Option<Vec<String>>,
     #[serde(default)]
     pub envs: Option<HashMap<String, String>>,
+    /// resource folders layered over src/main/resources e.g ["src/main/resources-prod"]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<Vec<String>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct Rules {
+    #[serde(default)]
+    pub bannedDependencies: Vec<String>,
+    #[serde(default)]
+    pub dependencyConvergence: bool,
+    #[serde(default)]
+    pub maxBytecodeVersion: Option<u32>,
+    #[serde(default)]
+    pub requireJavaVersion: Option<u32>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize, Clone, Eq, Hash, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Eq, Hash, PartialEq, JsonSchema)]
 pub struct Dependency {
     #[allow(non_snake_case)]
     pub groupId: String,
@@ -46,5 +168,12 @@ pub struct Dependency {
     pub artifactId: String,
     pub version: String,
     #[serde(default)]
+    #[schemars(extend("enum" = ["compile", "runtime", "provided", "test", null]))]
     pub scope: Option<String>,
+    /// e.g natives-linux
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classifier: Option<String>,
+    /// `groupId[:artifactId]` patterns, dropped from this dependency's transitive graph
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclusions: Option<Vec<String>>,
 }
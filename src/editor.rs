@@ -0,0 +1,111 @@
+use serde_json::Map;
+use serde_json::Value;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/* -------------------------------------------------------------------------------------------------
+Keeps the `.vscode/settings.json` (as generated by `grind new`) in sync with what grind installs, so
+the "Extension Pack for Java" can find library sources for go-to-definition (and the annotation
+processors' generated sources) etc. Only the keys we own are touched, any other user settings are
+left as is.
+------------------------------------------------------------------------------------------------- */
+
+const SETTINGS: &str = ".vscode/settings.json";
+const REFERENCED_LIBRARIES: &str = "java.project.referencedLibraries";
+const SOURCE_PATHS: &str = "java.project.sourcePaths";
+
+pub fn sync_vs_code_settings(sources: &HashMap<String, String>) {
+    // just like `add_source_path`, a project not set up for VS Code is left alone
+    if sources.is_empty() || !Path::new(SETTINGS).exists() {
+        return;
+    }
+
+    let mut settings = match self::read_settings() {
+        Ok(settings) => settings,
+        Err(e) => {
+            println!("⚠️ Unable to update {}: {}", SETTINGS, e);
+            return;
+        }
+    };
+
+    // the plain ["libs/*"] array form has to become the object form to attach sources
+    let include = match settings.get(REFERENCED_LIBRARIES) {
+        Some(Value::Array(include)) => Value::Array(include.clone()),
+        Some(Value::Object(existing)) => existing
+            .get("include")
+            .cloned()
+            .unwrap_or(json!(["libs/*", "libs-test/*"])),
+        _ => json!(["libs/*", "libs-test/*"]),
+    };
+
+    let mut libraries = match settings.get(REFERENCED_LIBRARIES) {
+        Some(Value::Object(existing)) => existing.clone(),
+        _ => Map::new(),
+    };
+    libraries.insert("include".to_string(), include);
+    libraries.insert("sources".to_string(), json!(sources));
+
+    settings.insert(REFERENCED_LIBRARIES.to_string(), Value::Object(libraries));
+
+    self::write_settings(settings);
+}
+
+pub fn add_source_path(path: &str) {
+    // e.g the annotation processors' generated sources, only for projects already set up for VS Code
+    if !Path::new(SETTINGS).exists() {
+        return;
+    }
+
+    let mut settings = match self::read_settings() {
+        Ok(settings) => settings,
+        Err(e) => {
+            println!("⚠️ Unable to update {}: {}", SETTINGS, e);
+            return;
+        }
+    };
+
+    let mut paths = match settings.get(SOURCE_PATHS) {
+        Some(Value::Array(paths)) => paths.clone(),
+        _ => vec![json!("src/main/java")],
+    };
+    if paths.iter().any(|p| p.as_str() == Some(path)) {
+        return;
+    }
+    paths.push(json!(path));
+
+    settings.insert(SOURCE_PATHS.to_string(), Value::Array(paths));
+
+    self::write_settings(settings);
+}
+
+fn read_settings() -> Result<Map<String, Value>, String> {
+    if !Path::new(SETTINGS).exists() {
+        return Ok(Map::new());
+    }
+
+    let raw = fs::read_to_string(SETTINGS).map_err(|e| e.to_string())?;
+    match serde_json::from_str::<Value>(&raw) {
+        Ok(Value::Object(settings)) => Ok(settings),
+        Ok(_) => Err("expected a JSON object".to_string()),
+        // NOTE: VS Code allows comments in settings.json, we'd rather skip than wipe them out
+        Err(e) => Err(format!("not plain JSON ({}), please update it by hand", e)),
+    }
+}
+
+fn write_settings(settings: Map<String, Value>) {
+    if let Err(e) = fs::create_dir_all(".vscode") {
+        println!("⚠️ Unable to create .vscode/: {}", e);
+        return;
+    }
+
+    match serde_json::to_string_pretty(&Value::Object(settings)) {
+        Ok(json) => {
+            if fs::write(SETTINGS, format!("{}\n", json)).is_ok() {
+                println!("🔃 {} synced..", SETTINGS);
+            }
+        }
+        Err(e) => println!("⚠️ Unable to update {}: {}", SETTINGS, e),
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+
+/* -------------------------------------------------------------------------------------------------
+Every command returns a `GrindError` on failure, `main` prints it and exits with its exit code, so
+`grind` can be relied on in CI and git hooks e.g `grind test || exit 1`:
+
+    1   anything else e.g unable to write a file
+    2   invalid command line usage (reported by clap)
+    3   the grind.yml is missing or invalid
+    4   dependencies could not be resolved, or failed the license/rules/signature checks
+    5   compilation (or packaging the jar) failed
+    6   one or more tests failed
+    7   an integrity check failed
+    8   vulnerabilities were found at or above the `--fail-on` threshold
+
+`grind run` and `grind task` exit with the program's own exit code instead.
+------------------------------------------------------------------------------------------------- */
+
+#[derive(Debug, PartialEq)]
+pub enum GrindError {
+    Config(String),
+    Resolution(String),
+    Compilation(String),
+    Tests(String),
+    Integrity(String),
+    Vulnerabilities(String),
+    // a program we ran on the user's behalf failed, passed straight through
+    Process(String, i32),
+    Other(String),
+}
+
+impl GrindError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GrindError::Other(_) => 1,
+            GrindError::Config(_) => 3,
+            GrindError::Resolution(_) => 4,
+            GrindError::Compilation(_) => 5,
+            GrindError::Tests(_) => 6,
+            GrindError::Integrity(_) => 7,
+            GrindError::Vulnerabilities(_) => 8,
+            GrindError::Process(_, code) => *code,
+        }
+    }
+}
+
+impl fmt::Display for GrindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrindError::Config(message)
+            | GrindError::Resolution(message)
+            | GrindError::Compilation(message)
+            | GrindError::Tests(message)
+            | GrindError::Integrity(message)
+            | GrindError::Vulnerabilities(message)
+            | GrindError::Process(message, _)
+            | GrindError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for GrindError {
+    fn from(message: String) -> Self {
+        GrindError::Other(message)
+    }
+}
+
+impl From<std::io::Error> for GrindError {
+    fn from(e: std::io::Error) -> Self {
+        GrindError::Other(format!("❌ {}", e))
+    }
+}
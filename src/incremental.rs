@@ -0,0 +1,545 @@
+use crate::java;
+use crate::util;
+use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/* -------------------------------------------------------------------------------------------------
+Incremental compilation of `src/main/java` into `target/`, rather than wiping target/ and compiling
+every source file on every build/run/test:
+
+    1. a "fingerprint" of the compiler flags, the javac in use and the jars in libs/, if that changes
+       everything is recompiled from scratch
+    2. otherwise only the sources whose hash changed (or that have no class files) are recompiled,
+       along with every source that (transitively) references one of their classes
+
+the references come straight from the class files' constant pool, i.e `CONSTANT_Class` entries plus
+any `Lcom/example/Foo;` types in the descriptors/signatures. The source a class came from is worked
+out from its package and the `SourceFile` attribute.
+
+NOTE: `static final` constants are inlined by javac, so a change to a constant's value is NOT seen
+by its users, delete target/ (or the STATE_FILE) to force a full rebuild.
+
+NOTE: classes compiled without a `SourceFile` attribute (e.g `-g:none` in the flags) can't be mapped
+back onto their sources, so every source that produced one is recompiled on every build.
+
+NOTE: classes generated by annotation processors (into target/generated-sources) are regenerated
+along with the source that triggered them, they're never treated as orphans.
+------------------------------------------------------------------------------------------------- */
+
+const STATE_FILE: &str = "cache/build-state.json";
+const SOURCES_FILE: &str = "cache/build-sources.txt";
+const SOURCE_DIR: &str = "src/main/java";
+const TARGET_DIR: &str = "target";
+pub const GENERATED_DIR: &str = "target/generated-sources";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BuildState {
+    fingerprint: String,
+    // path relative to src/main/java => sha256 of its contents
+    sources: BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ClassInfo {
+    pub name: String,
+    pub source: Option<String>,
+    pub references: HashSet<String>,
+    // has a `public static void main(String[])`, i.e it can be launched with `java`
+    pub has_main: bool,
+}
+
+pub fn compile(build_flags: &[String]) -> bool {
+    let previous = self::read_state();
+    let fingerprint = self::get_fingerprint(build_flags);
+    let sources = self::hash_sources();
+
+    let full = previous.fingerprint != fingerprint || !Path::new(TARGET_DIR).exists();
+
+    let success = if full {
+        self::compile_all(build_flags, &sources)
+    } else {
+        self::compile_changed(build_flags, &previous, &sources)
+    };
+
+    if success {
+        self::write_state(&BuildState {
+            fingerprint,
+            sources,
+        });
+    } else {
+        // whatever state target/ is in now, the next build starts from scratch
+        let _ = fs::remove_file(STATE_FILE);
+    }
+    success
+}
+
+fn compile_all(build_flags: &[String], sources: &BTreeMap<String, String>) -> bool {
+    let _ = fs::remove_dir_all(TARGET_DIR);
+    let _ = fs::create_dir_all(TARGET_DIR);
+
+    let files: Vec<String> = sources.keys().cloned().collect();
+    println!(
+        "==> 🔨 full build, compiling {} source file(s)...",
+        files.len()
+    );
+    self::javac(build_flags, "libs/*", &files)
+}
+
+fn compile_changed(
+    build_flags: &[String],
+    previous: &BuildState,
+    sources: &BTreeMap<String, String>,
+) -> bool {
+    let classes = self::read_class_index(TARGET_DIR);
+
+    let mut outputs: HashMap<&str, Vec<&ClassInfo>> = HashMap::new();
+    for info in classes.values() {
+        if let Some(source) = &info.source {
+            outputs.entry(source.as_str()).or_default().push(info);
+        }
+    }
+
+    // changed, new, or never produced any classes (e.g a previous build failed)
+    let mut dirty: HashSet<String> = sources
+        .iter()
+        .filter(|(path, hash)| {
+            previous.sources.get(*path) != Some(*hash) || !outputs.contains_key(path.as_str())
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+
+    // deleted sources still have classes (and dependents) that must go
+    dirty.extend(
+        previous
+            .sources
+            .keys()
+            .filter(|path| !sources.contains_key(*path))
+            .cloned(),
+    );
+
+    let dirty = self::get_dependents(dirty, &classes);
+
+    let unmapped = classes
+        .values()
+        .filter(|info| info.source.is_none())
+        .count();
+    if unmapped > 0 {
+        println!(
+            "⚠️ {} class file(s) have no SourceFile attribute (e.g -g:none), their sources are always recompiled",
+            unmapped
+        );
+    }
+
+    // remove the classes of anything we're about to recompile, plus any orphaned class files
+    for (path, info) in &classes {
+        let stale = match &info.source {
+            Some(source) if Path::new(GENERATED_DIR).join(source).exists() => false,
+            Some(source) => dirty.contains(source) || !sources.contains_key(source),
+            None => true,
+        };
+        if stale {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    let files: Vec<String> = sources
+        .keys()
+        .filter(|path| dirty.contains(*path))
+        .cloned()
+        .collect();
+
+    if files.is_empty() {
+        println!("✅ up to date, nothing to compile");
+        return true;
+    }
+
+    println!(
+        "==> 🔨 compiling {} of {} source file(s) (incremental)...",
+        files.len(),
+        sources.len()
+    );
+    self::javac(build_flags, "target:libs/*", &files)
+}
+
+fn get_dependents(
+    dirty: HashSet<String>,
+    classes: &BTreeMap<String, ClassInfo>,
+) -> HashSet<String> {
+    let mut dirty = dirty;
+
+    loop {
+        let dirty_classes: HashSet<&str> = classes
+            .values()
+            .filter(|info| info.source.as_ref().is_some_and(|s| dirty.contains(s)))
+            .map(|info| info.name.as_str())
+            .collect();
+
+        let dependents: Vec<String> = classes
+            .values()
+            .filter_map(|info| info.source.as_ref().map(|s| (s, info)))
+            .filter(|(source, info)| {
+                !dirty.contains(*source)
+                    && info
+                        .references
+                        .iter()
+                        .any(|r| dirty_classes.contains(r.as_str()))
+            })
+            .map(|(source, _)| source.clone())
+            .collect();
+
+        if dependents.is_empty() {
+            return dirty;
+        }
+        dirty.extend(dependents);
+    }
+}
+
+fn javac(build_flags: &[String], classpath: &str, files: &[String]) -> bool {
+    // the sources go into an @argfile, a large project easily blows the command line limit
+    let paths: Vec<String> = files
+        .iter()
+        .map(|f| format!("{}/{}", SOURCE_DIR, f))
+        .collect();
+
+    if let Err(e) = util::write_argfile(SOURCES_FILE, &paths) {
+        println!("❌ unable to write the sources: {}", e);
+        return false;
+    }
+
+    // javac won't create the `-s` directory for generated sources itself
+    for dir in build_flags
+        .windows(2)
+        .filter(|w| w[0] == "-s")
+        .map(|w| &w[1])
+    {
+        let _ = fs::create_dir_all(dir);
+    }
+
+    let status = util::exec(
+        java::command("javac")
+            .args(build_flags)
+            .args(["-d", TARGET_DIR, "-cp", classpath])
+            .arg(format!("@{}", SOURCES_FILE)),
+    );
+
+    match status {
+        Ok(status) => status.success(),
+        Err(e) => {
+            println!("{}", e);
+            false
+        }
+    }
+}
+
+fn get_fingerprint(build_flags: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(build_flags.join("\0").as_bytes());
+    // the resolved javac, so a `grind java use` (a symlink switch) is seen too
+    hasher.update(
+        java::get_javac_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default()
+            .as_bytes(),
+    );
+
+    let mut jars: Vec<String> = fs::read_dir("libs")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let size = entry.metadata().ok()?.len();
+            Some(format!("{}:{}", entry.file_name().to_string_lossy(), size))
+        })
+        .collect();
+    jars.sort();
+
+    for jar in jars {
+        hasher.update(jar.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn hash_sources() -> BTreeMap<String, String> {
+    self::list_files(SOURCE_DIR)
+        .into_iter()
+        .filter(|path| path.ends_with(".java"))
+        .filter_map(|path| {
+            let bytes = fs::read(Path::new(SOURCE_DIR).join(&path)).ok()?;
+            Some((path, format!("{:x}", Sha256::digest(&bytes))))
+        })
+        .collect()
+}
+
+fn list_files(dir: &str) -> Vec<String> {
+    let mut files: Vec<String> = WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            entry
+                .path()
+                .strip_prefix(dir)
+                .ok()
+                .map(|p| p.to_string_lossy().to_string())
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn read_state() -> BuildState {
+    fs::read_to_string(STATE_FILE)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn write_state(state: &BuildState) {
+    if let Ok(json) = serde_json::to_string_pretty(state)
+        && let Err(e) = fs::write(STATE_FILE, json)
+    {
+        println!("⚠️ unable to write {}: {}", STATE_FILE, e);
+    }
+}
+
+pub fn read_class_index(dir: &str) -> BTreeMap<String, ClassInfo> {
+    // class file path => what's inside, skipping the compiled tests
+    WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.path().to_path_buf())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "class"))
+        .filter(|path| !path.starts_with(Path::new(dir).join("test")))
+        .filter_map(|path| {
+            let bytes = fs::read(&path).ok()?;
+            let info = self::parse_class(&bytes).unwrap_or(ClassInfo {
+                name: String::new(),
+                source: None,
+                references: HashSet::new(),
+                has_main: false,
+            });
+            Some((path.to_string_lossy().to_string(), info))
+        })
+        .collect()
+}
+
+pub fn parse_class(bytes: &[u8]) -> Result<ClassInfo, String> {
+    /* ---------------------------------------------------------------------------------------------
+    only as much of the class file format as we need:
+
+        u4 magic, u2 minor, u2 major, u2 constant_pool_count, cp_info[count - 1],
+        u2 access_flags, u2 this_class, u2 super_class, u2 interfaces_count, u2[interfaces_count],
+        fields, methods, attributes (where the SourceFile attribute lives)
+    --------------------------------------------------------------------------------------------- */
+    let mut reader = Reader { bytes, pos: 0 };
+
+    if reader.u4()? != 0xCAFEBABE {
+        return Err("not a class file".to_string());
+    }
+    reader.skip(4)?;
+
+    let count = reader.u2()? as usize;
+    let mut utf8: HashMap<usize, String> = HashMap::new();
+    // constant pool index of a CONSTANT_Class => the index of its name
+    let mut class_refs: HashMap<usize, usize> = HashMap::new();
+
+    let mut index = 1;
+    while index < count {
+        let tag = reader.u1()?;
+        match tag {
+            1 => {
+                let len = reader.u2()? as usize;
+                let value = reader.take(len)?;
+                utf8.insert(index, String::from_utf8_lossy(value).to_string());
+            }
+            7 => {
+                class_refs.insert(index, reader.u2()? as usize);
+            }
+            8 | 16 | 19 | 20 => reader.skip(2)?,
+            15 => reader.skip(3)?,
+            3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => reader.skip(4)?,
+            // longs and doubles take up two slots
+            5 | 6 => {
+                reader.skip(8)?;
+                index += 1;
+            }
+            _ => return Err(format!("unknown constant pool tag {}", tag)),
+        }
+        index += 1;
+    }
+
+    reader.skip(2)?;
+    let this_class = reader.u2()? as usize;
+    reader.skip(2)?;
+
+    let interfaces = reader.u2()? as usize;
+    reader.skip(interfaces * 2)?;
+
+    // fields and methods share the same layout
+    let mut has_main = false;
+    for is_method in [false, true] {
+        let members = reader.u2()?;
+        for _ in 0..members {
+            let access = reader.u2()?;
+            let name = utf8.get(&(reader.u2()? as usize));
+            let descriptor = utf8.get(&(reader.u2()? as usize));
+
+            // ACC_PUBLIC | ACC_STATIC
+            if is_method
+                && access & 0x0009 == 0x0009
+                && name.is_some_and(|n| n == "main")
+                && descriptor.is_some_and(|d| d == "([Ljava/lang/String;)V")
+            {
+                has_main = true;
+            }
+            let attributes = reader.u2()?;
+            for _ in 0..attributes {
+                reader.skip(2)?;
+                let len = reader.u4()? as usize;
+                reader.skip(len)?;
+            }
+        }
+    }
+
+    let mut source_file = None;
+    let attributes = reader.u2()?;
+    for _ in 0..attributes {
+        let name = reader.u2()? as usize;
+        let len = reader.u4()? as usize;
+        if utf8.get(&name).map(|n| n.as_str()) == Some("SourceFile") && len == 2 {
+            source_file = utf8.get(&(reader.u2()? as usize)).cloned();
+        } else {
+            reader.skip(len)?;
+        }
+    }
+
+    // the name of a class entry, e.g `com/example/Foo`, `[Lcom/example/Foo;` for arrays
+    let name_of = |class_index: usize| -> Option<String> {
+        let name = utf8.get(&class_index)?;
+        Some(
+            name.trim_start_matches('[')
+                .trim_start_matches('L')
+                .trim_end_matches(';')
+                .to_string(),
+        )
+    };
+
+    let name = class_refs
+        .get(&this_class)
+        .and_then(|i| utf8.get(i))
+        .cloned()
+        .ok_or("this_class not found")?;
+
+    let descriptor = Regex::new(r"L([A-Za-z0-9_$/]+)[;<]").map_err(|e| e.to_string())?;
+
+    let mut references: HashSet<String> = class_refs.values().filter_map(|i| name_of(*i)).collect();
+    for value in utf8.values() {
+        for capture in descriptor.captures_iter(value) {
+            references.insert(capture[1].to_string());
+        }
+    }
+    references.remove(&name);
+
+    let source = source_file.map(|file| match name.rfind('/') {
+        Some(index) => format!("{}/{}", &name[..index], file),
+        None => file,
+    });
+
+    Ok(ClassInfo {
+        name,
+        source,
+        references,
+        has_main,
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos + len;
+        if end > self.bytes.len() {
+            return Err("unexpected end of class file".to_string());
+        }
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u1(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u2(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u4(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_class() {
+        let utf8 = |value: &str| {
+            let mut entry = vec![1u8];
+            entry.extend((value.len() as u16).to_be_bytes());
+            entry.extend(value.as_bytes());
+            entry
+        };
+
+        let mut bytes = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 65];
+        bytes.extend(13u16.to_be_bytes());
+        bytes.extend(utf8("com/example/Foo")); // 1
+        bytes.extend([7, 0, 1]); // 2
+        bytes.extend(utf8("java/lang/Object")); // 3
+        bytes.extend([7, 0, 3]); // 4
+        bytes.extend(utf8("SourceFile")); // 5
+        bytes.extend(utf8("Foo.java")); // 6
+        bytes.extend(utf8("(Lcom/example/Bar;)V")); // 7
+        bytes.extend([5, 0, 0, 0, 0, 0, 0, 0, 42]); // 8 (and 9)
+        bytes.extend(utf8("x")); // 10
+        bytes.extend(utf8("main")); // 11
+        bytes.extend(utf8("([Ljava/lang/String;)V")); // 12
+
+        // access flags, this_class, super_class, no interfaces or fields
+        bytes.extend([0, 33, 0, 2, 0, 4, 0, 0, 0, 0]);
+        // a single `public static void main(String[])` method
+        bytes.extend([0, 1, 0, 9, 0, 11, 0, 12, 0, 0]);
+        // a single SourceFile attribute
+        bytes.extend([0, 1, 0, 5, 0, 0, 0, 2, 0, 6]);
+
+        let info = parse_class(&bytes).unwrap();
+        assert_eq!(info.name, "com/example/Foo");
+        assert_eq!(info.source.as_deref(), Some("com/example/Foo.java"));
+        assert!(info.references.contains("java/lang/Object"));
+        assert!(info.references.contains("com/example/Bar"));
+        assert!(!info.references.contains("com/example/Foo"));
+        assert!(info.has_main);
+
+        assert!(parse_class(&bytes[..20]).is_err());
+    }
+}
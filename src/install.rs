@@ -1,8 +1,16 @@
 use crate::Grind;
 use crate::config::Dependency;
+use crate::editor;
+use crate::error::GrindError;
+use crate::incremental;
+use crate::license;
 use crate::lock;
+use crate::lock::Lock;
 use crate::pom;
+use crate::pom::EffectiveDependency;
 use crate::pom::PomId;
+use crate::rules;
+use crate::signature;
 use crate::util;
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -10,34 +18,135 @@ use std::collections::HashSet;
 use std::collections::VecDeque;
 
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering as AtomicOrdering;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
+use walkdir::WalkDir;
 
 // use crate::mock::FAKE_POM;
 
-pub async fn execute_install(grind: Grind) {
+// when set, POMs are only ever read from the local `cache/` e.g for `grind sbom`
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+// every POM is fetched once, then read from here
+pub const POM_CACHE: &str = "cache";
+
+// downloaded jars wait here until they're verified, see `verify_staged`
+const STAGING_DIR: &str = "cache/staging";
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, AtomicOrdering::Relaxed);
+}
+
+pub async fn execute_install(grind: Grind) -> Result<(), GrindError> {
+    let constraints = grind.project.constraints.clone().unwrap_or_default();
+    let processors = grind
+        .project
+        .annotationProcessors
+        .clone()
+        .unwrap_or_default();
+
     if let Ok(locked) = lock::get_lock_file()
         && grind.project.dependencies == locked.inputDeps
+        && constraints == locked.inputConstraints
+        && self::has_direct_deps(&grind.project.dependencies, &locked.lockedDeps)
+        && processors == locked.inputProcessors
+        && self::has_direct_deps(&processors, &locked.lockedProcessors)
+        && (locked.divergentDeps.is_some() || !self::needs_convergence(&grind))
     {
         println!("✅ No dependency changes detected, using grind.lock...");
-        for dep in locked.lockedDeps {
-            if let Err(e) = self::download_jar(&dep).await {
+        self::discard_staged(Path::new(".")).await;
+        if !license::check_policy(&grind, &locked.lockedDeps).await {
+            return Err(GrindError::Resolution(
+                "❌ install aborted, disallowed licenses found!".to_string(),
+            ));
+        }
+        if !rules::enforce(&grind, &locked.lockedDeps, locked.divergentDeps.as_deref()) {
+            return Err(GrindError::Resolution(
+                "❌ install aborted, project rules failed!".to_string(),
+            ));
+        }
+        // everything in the grind.lock is known to exist, so any failure here is a real one
+        let mut failed = 0;
+        for dep in &locked.lockedDeps {
+            if let Err(e) = self::download_jar(dep).await {
                 println!("⚠️ Failed to download [{:?}]: {:?}", dep, e);
+                failed += 1;
             }
         }
-        return;
+        if !rules::enforce_jars(&grind, &locked.lockedDeps) {
+            self::discard_staged(Path::new(".")).await;
+            return Err(GrindError::Resolution(
+                "❌ install aborted, project rules failed!".to_string(),
+            ));
+        }
+        failed += self::download_processors(&locked.lockedProcessors).await;
+        let downloaded = [locked.lockedDeps.as_slice(), &locked.lockedProcessors].concat();
+        if !self::verify_staged(&grind, &downloaded, Path::new(".")).await? {
+            return Err(GrindError::Resolution(
+                "❌ install aborted, untrusted dependencies found!".to_string(),
+            ));
+        }
+        self::download_extras(&grind, &locked.lockedDeps).await;
+
+        if failed > 0 {
+            return Err(GrindError::Resolution(format!(
+                "❌ {} locked dependencies failed to download",
+                failed
+            )));
+        }
+        return Ok(());
     }
     println!("⚙️ need to resolve all dependencies...");
-    let mut resolved = self::resolve_all_deps(grind.project.dependencies.clone()).await;
+    self::discard_staged(Path::new(".")).await;
+    let mut resolved =
+        self::resolve_all_deps(grind.project.dependencies.clone(), &constraints).await;
+
+    // keep the conflicts of the freshly resolved graph, before any collisions are "fixed"
+    let divergent = rules::get_divergent(&resolved);
+
+    // the jars of direct deps whose scope changed in the grind.yml e.g compile => test
+    let mut moved = Vec::new();
 
     if let Ok(locked) = lock::get_lock_file() {
         // we need to merge with existing downloaded deps along with the newer resolved deps
-        resolved.extend(locked.lockedDeps);
+        for dep in locked.lockedDeps {
+            let rescoped = self::apply_direct_scope(dep.clone(), &grind.project.dependencies);
+            if rescoped.scope != dep.scope {
+                moved.push(self::get_jar_path(&dep));
+            }
+            resolved.insert(rescoped);
+        }
     };
 
     resolved = self::filter_invalid(resolved);
 
-    resolved = self::fix_collisions(resolved);
+    resolved = self::fix_collisions(resolved, &constraints);
+
+    let forced = self::get_forced(&resolved, &constraints);
+    for dep in &forced {
+        println!(
+            "📌 forcing {}:{} to v{}",
+            dep.groupId, dep.artifactId, dep.version
+        );
+    }
+
+    let resolved: Vec<Dependency> = resolved.into_iter().collect();
+
+    if !license::check_policy(&grind, &resolved).await {
+        return Err(GrindError::Resolution(
+            "❌ install aborted, disallowed licenses found! grind.lock was not updated".to_string(),
+        ));
+    }
+
+    if !rules::enforce(&grind, &resolved, Some(&divergent)) {
+        return Err(GrindError::Resolution(
+            "❌ install aborted, project rules failed! grind.lock was not updated".to_string(),
+        ));
+    }
+
+    let mut failed = 0;
     for dep in &resolved {
         if let Err(e) = self::download_jar(dep).await {
             /*
@@ -60,20 +169,87 @@ pub async fn execute_install(grind: Grind) {
                 failed due to network issues.
             */
             println!("⚠️ Failed to download [{:?}]: {:?}", dep, e);
+            failed += 1;
         }
     }
-    lock::lock_file(&grind.project.dependencies, &resolved.into_iter().collect());
+
+    if !rules::enforce_jars(&grind, &resolved) {
+        self::discard_staged(Path::new(".")).await;
+        return Err(GrindError::Resolution(
+            "❌ install aborted, project rules failed! grind.lock was not updated".to_string(),
+        ));
+    }
+
+    // processors run inside javac, they never share a classpath (or collisions) with the project
+    let (locked_processors, _, _) =
+        self::resolve_graph(&self::get_processors(&grind), &constraints).await;
+    failed += self::download_processors(&locked_processors).await;
+
+    // a grind.lock listing jars that were never downloaded would only break the next build
+    if failed > 0 {
+        self::discard_staged(Path::new(".")).await;
+        return Err(GrindError::Resolution(format!(
+            "❌ {} dependencies failed to download, grind.lock was not updated",
+            failed
+        )));
+    }
+
+    let downloaded = [resolved.as_slice(), &locked_processors].concat();
+    if !self::verify_staged(&grind, &downloaded, Path::new(".")).await? {
+        return Err(GrindError::Resolution(
+            "❌ install aborted, untrusted dependencies found! grind.lock was not updated"
+                .to_string(),
+        ));
+    }
+
+    lock::lock_file(&Lock {
+        inputDeps: grind.project.dependencies.clone(),
+        inputConstraints: constraints,
+        lockedDeps: resolved.clone(),
+        forcedDeps: forced,
+        inputProcessors: processors,
+        lockedProcessors: locked_processors,
+        divergentDeps: Some(divergent),
+    });
+
+    let keep: HashSet<String> = resolved.iter().map(self::get_jar_path).collect();
+    for path in moved.iter().filter(|path| !keep.contains(*path)) {
+        let _ = fs::remove_file(path).await;
+    }
+
+    self::download_extras(&grind, &resolved).await;
+    Ok(())
 }
 
-pub async fn resolve_all_deps(initial_deps: Vec<Dependency>) -> HashSet<Dependency> {
+pub async fn resolve_all_deps(
+    initial_deps: Vec<Dependency>,
+    constraints: &[Dependency],
+) -> HashSet<Dependency> {
+    /* ---------------------------------------------------------------------------------------------
+    walks the graph breadth first, along with the exclusions inherited from the dependency that
+    pulled it in. Test (provided and processor) scopes are "sticky" i.e anything a test dependency
+    needs is also only needed for tests.
+
+    exclusions are NOT locked, they only shape the graph, so they're stripped off the resolved deps.
+    --------------------------------------------------------------------------------------------- */
     let mut resolved = HashSet::new();
 
     let mut to_visit = initial_deps
         .into_iter()
-        .filter(|dep| dep.scope.as_deref() != Some("test"))
+        .map(|dep| {
+            let exclusions = dep.exclusions.clone().unwrap_or_default();
+            (dep, exclusions)
+        })
         .collect::<VecDeque<_>>();
 
-    while let Some(dep) = to_visit.pop_front() {
+    while let Some((dep, exclusions)) = to_visit.pop_front() {
+        // a constraint wins over whatever version the graph asked for, so we must also walk the
+        // forced version's POM rather than the requested one
+        let dep = Dependency {
+            exclusions: None,
+            ..self::apply_constraints(dep, constraints)
+        };
+
         if resolved.contains(&dep) {
             continue;
         }
@@ -85,14 +261,51 @@ pub async fn resolve_all_deps(initial_deps: Vec<Dependency>) -> HashSet<Dependen
         // println!("DEBUG {:?}", transitive);
 
         for new_dep in transitive {
+            if let Some(pattern) = exclusions.iter().find(|p| rules::is_banned(p, &new_dep)) {
+                println!(
+                    "✂️ excluding {}:{} from {}:{} ('{}')",
+                    new_dep.groupId, new_dep.artifactId, dep.groupId, dep.artifactId, pattern
+                );
+                continue;
+            }
+
+            let new_dep = match dep.scope.as_deref() {
+                Some(scope @ ("test" | "provided" | "processor")) => Dependency {
+                    scope: Some(scope.to_string()),
+                    ..new_dep
+                },
+                _ => new_dep,
+            };
+
             if !resolved.contains(&new_dep) {
-                to_visit.push_back(new_dep);
+                to_visit.push_back((new_dep, exclusions.clone()));
             }
         }
     }
     resolved
 }
 
+pub async fn resolve_graph(
+    deps: &[Dependency],
+    constraints: &[Dependency],
+) -> (Vec<Dependency>, Vec<Dependency>, Vec<Dependency>) {
+    // a fresh resolve WITHOUT merging the grind.lock, returns the (resolved, forced, divergent) deps
+    let resolved = self::resolve_all_deps(deps.to_vec(), constraints).await;
+    let divergent = rules::get_divergent(&resolved);
+    let resolved = self::fix_collisions(self::filter_invalid(resolved), constraints);
+    let forced = self::get_forced(&resolved, constraints);
+
+    (resolved.into_iter().collect(), forced, divergent)
+}
+
+fn needs_convergence(grind: &Grind) -> bool {
+    grind
+        .project
+        .rules
+        .as_ref()
+        .is_some_and(|rules| rules.dependencyConvergence)
+}
+
 async fn fetch_deps(dep: &Dependency) -> Vec<Dependency> {
     let mut deps: Vec<Dependency> = Vec::new();
 
@@ -117,14 +330,13 @@ async fn fetch_deps(dep: &Dependency) -> Vec<Dependency> {
                 rdep.scope.as_deref().unwrap_or("compile")
             );
 
-            if let Some(v) = &rdep.scope
-                && v.contains("compile")
-            {
+            if self::is_compile(&rdep) {
                 deps.push(Dependency {
                     groupId: rdep.group_id,
                     artifactId: rdep.artifact_id,
                     version: rdep.version,
                     scope: rdep.scope,
+                    ..Default::default()
                 })
             }
         }
@@ -134,20 +346,72 @@ async fn fetch_deps(dep: &Dependency) -> Vec<Dependency> {
     deps
 }
 
-pub async fn get_pom(dep: Dependency) -> String {
-    // return FAKE_POM.to_string();
+fn is_compile(rdep: &EffectiveDependency) -> bool {
+    // optional dependencies are already dropped by the POM parsing
+    rdep.scope
+        .as_ref()
+        .is_some_and(|scope| scope.contains("compile"))
+}
+
+pub async fn get_dependency_graph(deps: &[Dependency]) -> HashMap<Dependency, Vec<Dependency>> {
+    /* ---------------------------------------------------------------------------------------------
+    re-walk the POMs of an already resolved set (e.g the grind.lock) to recover the "edges", any
+    transitive dependency is mapped back onto the version that was actually locked.
+    --------------------------------------------------------------------------------------------- */
+    let mut graph = HashMap::new();
+
+    for dep in deps {
+        let root_pom_id = PomId {
+            group_id: dep.groupId.clone(),
+            artifact_id: dep.artifactId.clone(),
+            version: dep.version.clone(),
+        };
 
-    if let Ok(_) = tokio::fs::create_dir_all("cache").await {
-        let pom_name = format!("{}_{}_{}.pom", dep.groupId, dep.artifactId, dep.version);
-        let local_path = format!("cache/{}", pom_name);
+        let mut visited = HashSet::new();
+        let mut edges = Vec::new();
 
-        if Path::new(&local_path).exists() {
-            // TODO: compute the POM's md5 and compare with remote md5 only
-            // use the cache if the remote file has not changed.
-            if let Ok(cached) = tokio::fs::read_to_string(local_path).await {
-                return cached;
+        if let Some(rdeps) = pom::get_effective_dependencies(root_pom_id, &mut visited).await {
+            // only the edges the resolver itself follows, see `fetch_deps`
+            for rdep in rdeps.into_iter().filter(self::is_compile) {
+                if let Some(locked) = deps
+                    .iter()
+                    .find(|d| d.groupId == rdep.group_id && d.artifactId == rdep.artifact_id)
+                    && !edges.contains(locked)
+                    && locked != dep
+                {
+                    edges.push(locked.clone());
+                }
             }
         }
+        graph.insert(dep.clone(), edges);
+    }
+    graph
+}
+
+pub async fn get_pom(dep: Dependency) -> String {
+    self::get_pom_from(dep, Path::new(POM_CACHE)).await
+}
+
+pub async fn get_pom_from(dep: Dependency, cache: &Path) -> String {
+    // return FAKE_POM.to_string();
+    let pom_name = format!("{}_{}_{}.pom", dep.groupId, dep.artifactId, dep.version);
+    let local_path = cache.join(pom_name);
+
+    // TODO: compute the POM's md5 and compare with remote md5 only
+    // use the cache if the remote file has not changed.
+    if tokio::fs::create_dir_all(cache).await.is_ok()
+        && local_path.exists()
+        && let Ok(cached) = tokio::fs::read_to_string(&local_path).await
+    {
+        return cached;
+    }
+
+    if OFFLINE.load(AtomicOrdering::Relaxed) {
+        eprintln!(
+            "⚠️ offline: no cached POM for {}:{}:{}, try `grind install` first",
+            dep.groupId, dep.artifactId, dep.version
+        );
+        return "error!".to_string();
     }
 
     println!("🌎 ==> fetching POM.xml for {}", dep.artifactId);
@@ -162,9 +426,7 @@ pub async fn get_pom(dep: Dependency) -> String {
 
         return match body {
             Ok(b) => {
-                let pom_name = format!("{}_{}_{}.pom", dep.groupId, dep.artifactId, dep.version);
-                let local_path = format!("cache/{}", pom_name);
-                tokio::fs::write(local_path, b.clone())
+                tokio::fs::write(&local_path, b.clone())
                     .await
                     .unwrap_or_else(|e| eprintln!("⚠️ Failed to write file: {}", e));
                 b
@@ -183,31 +445,171 @@ fn build_pom_url(group: &str, artifact: &str, version: &str) -> String {
     )
 }
 
-async fn download_jar(dep: &Dependency) -> Result<(), String> {
+pub fn get_jar_path(dep: &Dependency) -> String {
+    // test dependencies (and processors) live in their own folder so they never end up on the main
+    // classpath
+    let dir = match dep.scope.as_deref() {
+        Some("test") => "libs-test",
+        Some("processor") => "libs-processors",
+        _ => "libs",
+    };
+    format!(
+        "{}/{}_{}_{}{}.jar",
+        dir,
+        dep.groupId,
+        dep.artifactId,
+        dep.version,
+        self::get_classifier_suffix(dep)
+    )
+}
+
+pub fn build_jar_url(dep: &Dependency) -> String {
     let group_path = dep.groupId.replace('.', "/");
-    let artifact = &dep.artifactId;
-    let version = &dep.version;
+    format!(
+        "https://repo1.maven.org/maven2/{}/{}/{}/{}-{}{}.jar",
+        group_path,
+        dep.artifactId,
+        dep.version,
+        dep.artifactId,
+        dep.version,
+        self::get_classifier_suffix(dep)
+    )
+}
+
+fn has_direct_deps(direct: &[Dependency], locked: &[Dependency]) -> bool {
+    // e.g older lock files never resolved the test dependencies
+    direct.iter().all(|d| {
+        locked
+            .iter()
+            .any(|l| l.groupId == d.groupId && l.artifactId == d.artifactId)
+    })
+}
+
+pub fn get_classifier_suffix(dep: &Dependency) -> String {
+    dep.classifier
+        .as_ref()
+        .map(|c| format!("-{}", c))
+        .unwrap_or_default()
+}
+
+pub fn get_provided_jars() -> Vec<String> {
+    // provided jars are on the compile classpath, but are expected to be supplied at runtime
+    lock::get_lock_file()
+        .map(|l| l.lockedDeps)
+        .unwrap_or_default()
+        .iter()
+        .filter(|dep| dep.scope.as_deref() == Some("provided"))
+        .map(self::get_jar_path)
+        .collect()
+}
+
+fn get_processors(grind: &Grind) -> Vec<Dependency> {
+    // "processor" is an internal scope, it's never written to the grind.yml
+    grind
+        .project
+        .annotationProcessors
+        .iter()
+        .flatten()
+        .map(|dep| Dependency {
+            scope: Some("processor".to_string()),
+            ..dep.clone()
+        })
+        .collect()
+}
+
+pub fn get_processor_jars() -> Vec<String> {
+    lock::get_lock_file()
+        .map(|l| l.lockedProcessors)
+        .unwrap_or_default()
+        .iter()
+        .map(self::get_jar_path)
+        .collect()
+}
+
+async fn download_processors(processors: &[Dependency]) -> usize {
+    let mut failed = 0;
+    for dep in processors {
+        if let Err(e) = self::download_jar(dep).await {
+            println!("⚠️ Failed to download [{:?}]: {:?}", dep, e);
+            failed += 1;
+        }
+    }
 
-    let jar_name = format!("{}_{}_{}.jar", dep.groupId, artifact, version);
-    let local_path = format!("libs/{}", jar_name);
+    if !processors.is_empty() {
+        editor::add_source_path(incremental::GENERATED_DIR);
+    }
+    failed
+}
 
-    if Path::new(&local_path).exists() {
-        println!("📦 Already exists, skipping: {}", local_path);
+pub fn get_staged_path(dep: &Dependency) -> String {
+    format!("{}/{}", STAGING_DIR, self::get_jar_path(dep))
+}
+
+pub fn get_downloaded_path(dep: &Dependency) -> String {
+    // a jar that is still staged (i.e not verified yet) is the one that gets checked
+    let staged = self::get_staged_path(dep);
+    if Path::new(&staged).exists() {
+        staged
+    } else {
+        self::get_jar_path(dep)
+    }
+}
+
+async fn download_jar(dep: &Dependency) -> Result<(), String> {
+    if Path::new(&self::get_jar_path(dep)).exists() {
+        println!("📦 Already exists, skipping: {}", self::get_jar_path(dep));
         return Ok(());
     }
 
-    fs::create_dir_all("libs")
-        .await
-        .map_err(|e| e.to_string())?;
+    let local_path = self::get_staged_path(dep);
+
+    if let Some(dir) = Path::new(&local_path).parent() {
+        fs::create_dir_all(dir).await.map_err(|e| e.to_string())?;
+    }
+
+    self::download_file(&self::build_jar_url(dep), &local_path).await
+}
+
+async fn verify_staged(
+    grind: &Grind,
+    deps: &[Dependency],
+    root: &Path,
+) -> Result<bool, GrindError> {
+    /* ---------------------------------------------------------------------------------------------
+    new jars are downloaded into cache/staging/ (mirroring libs/, libs-test/ etc) and only moved into
+    place once their signatures check out, otherwise a failed install would still leave untrusted
+    jars on the classpath for the next `grind build`.
+    --------------------------------------------------------------------------------------------- */
+    if !signature::verify_all(&grind.project, deps).await {
+        self::discard_staged(root).await;
+        return Ok(false);
+    }
 
-    let url = format!(
-        "https://repo1.maven.org/maven2/{}/{}/{}/{}-{}.jar",
-        group_path, artifact, version, artifact, version
-    );
+    let staging = root.join(STAGING_DIR);
+    for entry in WalkDir::new(&staging).into_iter().flatten() {
+        let Ok(relative) = entry.path().strip_prefix(&staging) else {
+            continue;
+        };
+        let destination = root.join(relative);
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&destination).await?;
+        } else {
+            fs::rename(entry.path(), &destination).await?;
+        }
+    }
+    self::discard_staged(root).await;
+    Ok(true)
+}
+
+async fn discard_staged(root: &Path) {
+    let _ = fs::remove_dir_all(root.join(STAGING_DIR)).await;
+}
 
+async fn download_file(url: &str, local_path: &str) -> Result<(), String> {
     println!("📥 Downloading: {}", url);
 
-    let resp = reqwest::get(&url).await.map_err(|e| e.to_string())?;
+    let resp = reqwest::get(url).await.map_err(|e| e.to_string())?;
 
     if !resp.status().is_success() {
         return Err(format!(
@@ -217,7 +619,7 @@ async fn download_jar(dep: &Dependency) -> Result<(), String> {
     }
 
     let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
-    let mut file = fs::File::create(&local_path)
+    let mut file = fs::File::create(local_path)
         .await
         .map_err(|e| e.to_string())?;
     file.write_all(&bytes).await.map_err(|e| e.to_string())?;
@@ -225,6 +627,64 @@ async fn download_jar(dep: &Dependency) -> Result<(), String> {
     Ok(())
 }
 
+pub fn get_extra_path(dep: &Dependency, classifier: &str) -> String {
+    format!(
+        "libs-sources/{}_{}_{}-{}.jar",
+        dep.groupId, dep.artifactId, dep.version, classifier
+    )
+}
+
+async fn download_extras(grind: &Grind, deps: &[Dependency]) {
+    /* ---------------------------------------------------------------------------------------------
+    optionally fetch the `-sources.jar` and `-javadoc.jar` for every locked dependency, these live
+    in their own folder (NOT libs/) so they never end up on the classpath or inside a bundle.
+    --------------------------------------------------------------------------------------------- */
+    let mut classifiers = Vec::new();
+    if grind.project.downloadSources == Some(true) {
+        classifiers.push("sources");
+    }
+    if grind.project.downloadJavadoc == Some(true) {
+        classifiers.push("javadoc");
+    }
+    if classifiers.is_empty() {
+        return;
+    }
+
+    if let Err(e) = fs::create_dir_all("libs-sources").await {
+        println!("⚠️ Unable to create libs-sources/: {}", e);
+        return;
+    }
+
+    let mut sources = HashMap::new();
+
+    // e.g `natives-linux` jars don't come with their own sources
+    for dep in deps.iter().filter(|dep| dep.classifier.is_none()) {
+        for classifier in &classifiers {
+            let local_path = self::get_extra_path(dep, classifier);
+
+            if !Path::new(&local_path).exists() {
+                let url = self::build_jar_url(dep).replace(
+                    &format!("-{}.jar", dep.version),
+                    &format!("-{}-{}.jar", dep.version, classifier),
+                );
+                if let Err(e) = self::download_file(&url, &local_path).await {
+                    println!(
+                        "ℹ️ no {} jar for {}:{}:{}: {}",
+                        classifier, dep.groupId, dep.artifactId, dep.version, e
+                    );
+                    continue;
+                }
+            }
+
+            if *classifier == "sources" {
+                sources.insert(self::get_jar_path(dep), local_path);
+            }
+        }
+    }
+
+    editor::sync_vs_code_settings(&sources);
+}
+
 fn filter_invalid(deps: HashSet<Dependency>) -> HashSet<Dependency> {
     deps.into_iter()
         .filter(|dep| {
@@ -240,7 +700,30 @@ fn filter_invalid(deps: HashSet<Dependency>) -> HashSet<Dependency> {
         .collect()
 }
 
-fn fix_collisions(deps: HashSet<Dependency>) -> HashSet<Dependency> {
+fn apply_constraints(dep: Dependency, constraints: &[Dependency]) -> Dependency {
+    match self::find_constraint(&dep, constraints) {
+        Some(forced) => Dependency {
+            version: forced.version.clone(),
+            ..dep
+        },
+        None => dep,
+    }
+}
+
+fn find_constraint<'a>(dep: &Dependency, constraints: &'a [Dependency]) -> Option<&'a Dependency> {
+    constraints
+        .iter()
+        .find(|c| c.groupId == dep.groupId && c.artifactId == dep.artifactId)
+}
+
+fn get_forced(deps: &HashSet<Dependency>, constraints: &[Dependency]) -> Vec<Dependency> {
+    deps.iter()
+        .filter(|dep| self::find_constraint(dep, constraints).is_some())
+        .cloned()
+        .collect()
+}
+
+fn fix_collisions(deps: HashSet<Dependency>, constraints: &[Dependency]) -> HashSet<Dependency> {
     /* ---------------------------------------------------------------------------------------------
     modern build tools and including the latest versions of maven use the "newest" version wins
     when it comes to dependency "collisions" (different versions of the same artifact).
@@ -249,23 +732,70 @@ fn fix_collisions(deps: HashSet<Dependency>) -> HashSet<Dependency> {
 
     However this needs to be combined with a "lock" file to "freeze" the fully resolved tree, this
     makes it deterministic and reproducible.
+
+    Constraints (forced versions) from the grind.yml always win, regardless of which version is
+    newest or what any POM's <dependencyManagement> asked for.
     --------------------------------------------------------------------------------------------- */
-    let mut latest_versions: HashMap<(String, String), Dependency> = HashMap::new();
+    let mut latest_versions: HashMap<(String, String, Option<String>), Dependency> = HashMap::new();
 
     for dep in deps {
-        let key = (dep.groupId.clone(), dep.artifactId.clone());
+        let key = (
+            dep.groupId.clone(),
+            dep.artifactId.clone(),
+            dep.classifier.clone(),
+        );
 
         latest_versions
             .entry(key)
             .and_modify(|existing| {
+                if existing.version != dep.version {
+                    println!(
+                        "⚖️ version collision for {}:{} ({} vs {}), newest wins",
+                        dep.groupId, dep.artifactId, existing.version, dep.version
+                    );
+                }
+                // the "widest" scope wins, e.g a compile dep also used by a test dep stays compile
+                let scope = if self::get_scope_rank(&dep) < self::get_scope_rank(existing) {
+                    dep.scope.clone()
+                } else {
+                    existing.scope.clone()
+                };
                 if self::is_version_newer(&existing.version, &dep.version) {
                     *existing = dep.clone();
                 }
+                existing.scope = scope;
             })
             .or_insert(dep);
     }
 
-    latest_versions.into_values().collect()
+    latest_versions
+        .into_values()
+        .map(|dep| self::apply_constraints(dep, constraints))
+        .collect()
+}
+
+fn apply_direct_scope(dep: Dependency, direct: &[Dependency]) -> Dependency {
+    // the grind.yml has the final say on a direct dependency's scope, otherwise a locked compile
+    // dep would always "widen" it back when it's narrowed to e.g test
+    match direct.iter().find(|d| {
+        d.groupId == dep.groupId && d.artifactId == dep.artifactId && d.classifier == dep.classifier
+    }) {
+        Some(declared) => Dependency {
+            scope: declared.scope.clone(),
+            ..dep
+        },
+        None => dep,
+    }
+}
+
+fn get_scope_rank(dep: &Dependency) -> u8 {
+    // widest first: compile, runtime (not needed by javac, but still shipped), provided, test
+    match dep.scope.as_deref() {
+        Some("test") => 3,
+        Some("provided") => 2,
+        Some("runtime") => 1,
+        _ => 0,
+    }
 }
 
 fn is_version_newer(source: &str, target: &str) -> bool {
@@ -286,6 +816,7 @@ mod tests {
             artifactId: "xml-resolver".to_string(),
             version: String::from("1.1"),
             scope: None,
+            ..Default::default()
         });
 
         deps.insert(Dependency {
@@ -293,6 +824,7 @@ mod tests {
             artifactId: "xml-resolver".to_string(),
             version: String::from("1.2"), // <- Should be kept, 1.2 is newer
             scope: None,
+            ..Default::default()
         });
 
         deps.insert(Dependency {
@@ -300,6 +832,7 @@ mod tests {
             artifactId: "lib2".to_string(),
             version: String::from("0.9.1"), // <- Should be kept, unique
             scope: None,
+            ..Default::default()
         });
 
         deps.insert(Dependency {
@@ -307,6 +840,7 @@ mod tests {
             artifactId: "lib3".to_string(),
             version: String::from("3.1.4"),
             scope: None,
+            ..Default::default()
         });
 
         deps.insert(Dependency {
@@ -314,9 +848,10 @@ mod tests {
             artifactId: "lib3".to_string(),
             version: String::from("3.2.0"), // <- Should be kept, 3.2.0 is newer
             scope: None,
+            ..Default::default()
         });
 
-        let result = fix_collisions(deps);
+        let result = fix_collisions(deps, &[]);
 
         // Should contain only 3 dependencies: latest of xml-resolver, lib2, and lib3
         assert_eq!(result.len(), 3);
@@ -328,18 +863,21 @@ mod tests {
                 artifactId: "xml-resolver".to_string(),
                 version: String::from("1.2"),
                 scope: None,
+                ..Default::default()
             },
             Dependency {
                 groupId: "com.example".to_string(),
                 artifactId: "lib2".to_string(),
                 version: String::from("0.9.1"),
                 scope: None,
+                ..Default::default()
             },
             Dependency {
                 groupId: "org.other".to_string(),
                 artifactId: "lib3".to_string(),
                 version: String::from("3.2.0"),
                 scope: None,
+                ..Default::default()
             },
         ];
 
@@ -351,4 +889,181 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_constraints_override_newest() {
+        let mut deps = HashSet::new();
+
+        deps.insert(Dependency {
+            groupId: "com.fasterxml.jackson.core".to_string(),
+            artifactId: "jackson-databind".to_string(),
+            version: String::from("2.15.0"),
+            scope: None,
+            ..Default::default()
+        });
+
+        deps.insert(Dependency {
+            groupId: "com.fasterxml.jackson.core".to_string(),
+            artifactId: "jackson-databind".to_string(),
+            version: String::from("2.18.0"),
+            scope: None,
+            ..Default::default()
+        });
+
+        let constraints = vec![Dependency {
+            groupId: "com.fasterxml.jackson.core".to_string(),
+            artifactId: "jackson-databind".to_string(),
+            version: String::from("2.17.2"), // <- Should be kept, even though 2.18.0 is newer
+            scope: None,
+            ..Default::default()
+        }];
+
+        let result = fix_collisions(deps, &constraints);
+
+        assert_eq!(result.len(), 1);
+        assert!(result.contains(&Dependency {
+            groupId: "com.fasterxml.jackson.core".to_string(),
+            artifactId: "jackson-databind".to_string(),
+            version: String::from("2.17.2"),
+            scope: None,
+            ..Default::default()
+        }));
+        assert_eq!(get_forced(&result, &constraints).len(), 1);
+    }
+
+    #[test]
+    fn test_scopes_and_classifiers() {
+        let hamcrest = |version: &str, scope: &str| Dependency {
+            groupId: "org.hamcrest".to_string(),
+            artifactId: "hamcrest-core".to_string(),
+            version: version.to_string(),
+            scope: Some(scope.to_string()),
+            ..Default::default()
+        };
+
+        // needed by both a test and a compile dependency, so it must stay on the main classpath
+        let mut deps = HashSet::new();
+        deps.insert(hamcrest("1.3", "compile"));
+        deps.insert(hamcrest("2.2", "test"));
+
+        let result = fix_collisions(deps, &[]);
+        assert_eq!(result.len(), 1);
+        assert!(result.contains(&hamcrest("2.2", "compile")));
+
+        // runtime is still shipped, so it's wider than provided but narrower than compile
+        let mut deps = HashSet::new();
+        deps.insert(hamcrest("2.2", "runtime"));
+        deps.insert(hamcrest("2.2", "provided"));
+        assert!(fix_collisions(deps, &[]).contains(&hamcrest("2.2", "runtime")));
+
+        let mut deps = HashSet::new();
+        deps.insert(hamcrest("2.2", "runtime"));
+        deps.insert(hamcrest("2.2", "compile"));
+        assert!(fix_collisions(deps, &[]).contains(&hamcrest("2.2", "compile")));
+
+        assert_eq!(
+            get_jar_path(&hamcrest("1.3", "test")),
+            "libs-test/org.hamcrest_hamcrest-core_1.3.jar"
+        );
+
+        let natives = Dependency {
+            groupId: "org.lwjgl".to_string(),
+            artifactId: "lwjgl".to_string(),
+            version: "3.3.4".to_string(),
+            classifier: Some("natives-linux".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_jar_path(&natives),
+            "libs/org.lwjgl_lwjgl_3.3.4-natives-linux.jar"
+        );
+        assert!(build_jar_url(&natives).ends_with("/lwjgl/3.3.4/lwjgl-3.3.4-natives-linux.jar"));
+    }
+
+    #[test]
+    fn test_narrowing_a_direct_scope() {
+        let junit = |scope: &str| Dependency {
+            groupId: "junit".to_string(),
+            artifactId: "junit".to_string(),
+            version: "4.13.2".to_string(),
+            scope: Some(scope.to_string()),
+            ..Default::default()
+        };
+
+        // locked as compile, since moved to test in the grind.yml
+        let direct = vec![junit("test")];
+        let mut deps = HashSet::new();
+        deps.insert(junit("test"));
+        deps.insert(apply_direct_scope(junit("compile"), &direct));
+
+        let result = fix_collisions(deps, &[]);
+        assert_eq!(result.len(), 1);
+        assert!(result.contains(&junit("test")));
+
+        // transitive deps are left alone
+        let other = Dependency {
+            artifactId: "junit-dep".to_string(),
+            ..junit("compile")
+        };
+        assert_eq!(apply_direct_scope(other.clone(), &direct), other);
+    }
+
+    #[tokio::test]
+    async fn test_failed_verification_leaves_libs_unchanged() {
+        let root = std::env::temp_dir().join(format!("grind-staging-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join(STAGING_DIR).join("libs")).unwrap();
+        std::fs::create_dir_all(root.join("libs")).unwrap();
+        std::fs::write(root.join("libs/org.example_old_1.0.jar"), "old").unwrap();
+        std::fs::write(
+            root.join(STAGING_DIR).join("libs/org.example_new_1.0.jar"),
+            "new",
+        )
+        .unwrap();
+
+        // strict, but no trustedKeys at all, so the new jar can't be verified
+        let raw = r#"
+project:
+  groupId: "com.example"
+  artifactId: "demo"
+  version: "1.0.0"
+  name: "demo"
+  description: "demo"
+  dependencies: []
+  tasks: {}
+  verifySignatures: strict
+"#;
+        let grind: Grind = serde_yaml::from_str(raw).unwrap();
+        let deps = vec![Dependency {
+            groupId: "org.example".to_string(),
+            artifactId: "new".to_string(),
+            version: "1.0".to_string(),
+            ..Default::default()
+        }];
+
+        assert!(!verify_staged(&grind, &deps, &root).await.unwrap());
+        let libs = std::fs::read_dir(root.join("libs"))
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(libs, vec!["org.example_old_1.0.jar"]);
+        assert!(!root.join(STAGING_DIR).exists());
+
+        // once verified (no verifySignatures), the staged jar is moved into place
+        std::fs::create_dir_all(root.join(STAGING_DIR).join("libs")).unwrap();
+        std::fs::write(
+            root.join(STAGING_DIR).join("libs/org.example_new_1.0.jar"),
+            "new",
+        )
+        .unwrap();
+        let raw = raw.replace("  verifySignatures: strict\n", "");
+        let grind: Grind = serde_yaml::from_str(&raw).unwrap();
+
+        assert!(verify_staged(&grind, &deps, &root).await.unwrap());
+        assert!(root.join("libs/org.example_new_1.0.jar").exists());
+        assert!(!root.join(STAGING_DIR).exists());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
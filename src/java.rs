@@ -1,6 +1,7 @@
+use crate::Grind;
+use crate::error::GrindError;
 use crate::util;
 use crate::util::GrindPath;
-use crate::util::shell_custom_path;
 use futures_util::StreamExt;
 use regex::Regex;
 use reqwest::Client;
@@ -14,6 +15,20 @@ use std::fs::OpenOptions;
 use std::io;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/* -------------------------------------------------------------------------------------------------
+a project can pin its JDK, either with `jdk: 21` in the grind.yml or a `.grind-jdk` file containing
+just the version. Rather than switching the JDK globally (see `grind java use`), every javac, java
+and jar that grind runs for the project then uses the absolute path into ~/.grind/jdks/v21/bin, so
+projects on different JDKs can live side by side without touching the user's shell.
+------------------------------------------------------------------------------------------------- */
+
+const JDK_FILE: &str = ".grind-jdk";
+
+// the home of the pinned JDK e.g ~/.grind/jdks/v21, set once per invocation
+static PINNED_JDK: OnceLock<PathBuf> = OnceLock::new();
 
 #[allow(non_snake_case)]
 #[derive(Debug, Deserialize)]
@@ -21,16 +36,16 @@ struct JavaMetaData {
     available_releases: Vec<i32>,
 }
 
-pub async fn list() {
+pub async fn list() -> Result<(), GrindError> {
     /* ---------------------------------------------------------------------------------------------
     call remote API and show all available JDK versions
     --------------------------------------------------------------------------------------------- */
-    if let Err(s) = self::get_list().await {
-        println!("❌ Unable to fetch the list of JDK verions: {}", s);
-    }
+    self::get_list().await.map_err(|s| {
+        GrindError::Other(format!("❌ Unable to fetch the list of JDK verions: {}", s))
+    })
 }
 
-pub fn current() {
+pub fn current() -> Result<(), GrindError> {
     /* ---------------------------------------------------------------------------------------------
     if the symlink exits, check where it points to and show that as the current
     otherwise check the system wide version (if it exists) and list that version
@@ -48,16 +63,34 @@ pub fn current() {
                 "🖥️  [System Installed JDK]"
             };
 
-            if let Ok(version) = self::get_java_version(include_grind_path) {
-                println!("{} | v{}", managed_jdk, version);
-            } else {
-                println!("❌ Unable to detect any Java on this system");
+            match self::get_java_version(include_grind_path) {
+                Ok(version) => println!("{} | v{}", managed_jdk, version),
+                Err(_) => {
+                    return Err(GrindError::Other(
+                        "❌ Unable to detect any Java on this system".to_string(),
+                    ));
+                }
             }
         }
         Err(e) => {
-            println!("Error, unable to inspect grind JDK! {}", e);
+            return Err(GrindError::Other(format!(
+                "❌ Unable to inspect grind JDK! {}",
+                e
+            )));
         }
     }
+
+    // inside a project, its pinned JDK is what grind actually uses
+    if Path::new("grind.yml").exists()
+        && let Ok(grind) = util::parse_grind_file()
+        && let Ok(Some(version)) = self::get_pinned_version(&grind)
+    {
+        println!(
+            "📌 [Project Pinned JDK] | v{} (~/.grind/jdks/v{})",
+            version, version
+        );
+    }
+    Ok(())
 }
 
 async fn get_list() -> Result<(), String> {
@@ -87,7 +120,7 @@ async fn get_list() -> Result<(), String> {
     Ok(())
 }
 
-pub async fn _use(version: String) {
+pub async fn _use(version: String) -> Result<(), GrindError> {
     let version = version.trim_start_matches('v');
     match self::get_jdk_detail(&version).await {
         Ok(download_link) => {
@@ -98,13 +131,18 @@ pub async fn _use(version: String) {
             create the symlink (overwrite even if one exists) e.g ~/.grind/jdks/current -> ./v22/bin
             make sure that the ~/.bashrc contains the PATH if not add it
             ------------------------------------------------------------------------------------- */
-            if let Err(e) = self::run_install(&version, &download_link).await {
-                println!("❌ Unable to setup and install JDK: {}", e);
-            } else {
-                println!("✅ JDK v{} setup is completed!", &version);
-            }
+            self::run_install(&version, &download_link)
+                .await
+                .map_err(|e| {
+                    GrindError::Other(format!("❌ Unable to setup and install JDK: {}", e))
+                })?;
+            println!("✅ JDK v{} setup is completed!", &version);
+            Ok(())
         }
-        Err(error) => println!("❌ Unable to fetch JDK version metadata: {}", error),
+        Err(error) => Err(GrindError::Other(format!(
+            "❌ Unable to fetch JDK version metadata: {}",
+            error
+        ))),
     }
 }
 
@@ -143,14 +181,20 @@ fn is_bashrc_exist() -> Result<bool, String> {
     Ok(bashrc.contains("# GRIND-JDK-PATH"))
 }
 
-fn get_java_version(include_grind_path: bool) -> Result<String, String> {
+pub fn get_java_version(include_grind_path: bool) -> Result<String, String> {
     let grind_path_option = if include_grind_path {
         GrindPath::Include
     } else {
         GrindPath::Exlude
     };
 
-    let out = shell_custom_path("java --version", grind_path_option);
+    // no shell involved, the PATH is only used to look up `java` when no JDK is pinned
+    let mut command = self::command("java");
+    command.arg("--version");
+    if PINNED_JDK.get().is_none() {
+        command.env("PATH", util::get_custom_path(grind_path_option));
+    }
+    let out = util::exec_output(&mut command)?;
 
     let re = Regex::new(r#"(\d+\.\d+\.\d+.*\w)"#).map_err(|e| e.to_string())?;
 
@@ -161,6 +205,126 @@ fn get_java_version(include_grind_path: bool) -> Result<String, String> {
     }
 }
 
+pub fn get_pinned_version(grind: &Grind) -> Result<Option<u32>, String> {
+    let file = match fs::read_to_string(JDK_FILE) {
+        Ok(raw) => {
+            let version = raw.trim().trim_start_matches('v');
+            Some(
+                version
+                    .parse::<u32>()
+                    .map_err(|_| format!("❌ invalid JDK version '{}' in {}", version, JDK_FILE))?,
+            )
+        }
+        Err(_) => None,
+    };
+
+    match (grind.project.jdk, file) {
+        (Some(jdk), Some(file)) if jdk != file => {
+            println!(
+                "⚠️ the grind.yml pins JDK v{} but {} says v{}, using v{}",
+                jdk, JDK_FILE, file, jdk
+            );
+            Ok(Some(jdk))
+        }
+        (Some(jdk), _) => Ok(Some(jdk)),
+        (None, file) => Ok(file),
+    }
+}
+
+pub async fn use_pinned_jdk(grind: &Grind) -> Result<(), String> {
+    let Some(version) = self::get_pinned_version(grind)? else {
+        return Ok(());
+    };
+
+    let home = self::get_jdk_home(version)?;
+
+    if !home.join("bin").join("javac").exists() {
+        println!("==> 📌 the project pins JDK v{}, installing it...", version);
+        let install = async {
+            let version = version.to_string();
+            let download_link = self::get_jdk_detail(&version).await?;
+            self::create_jdk_dir()?;
+            self::download_sdk(&version, download_link).await
+        };
+        install
+            .await
+            .map_err(|e| format!("❌ unable to install JDK v{}: {}", version, e))?;
+    }
+
+    let home = self::get_jdk_home(version)?;
+    if !home.join("bin").join("javac").exists() {
+        return Err(format!(
+            "❌ JDK v{} in {} has no bin/javac, try removing it",
+            version,
+            home.display()
+        ));
+    }
+
+    let _ = PINNED_JDK.set(home);
+    Ok(())
+}
+
+fn get_jdk_home(version: u32) -> Result<PathBuf, String> {
+    let home = util::expand_tilde(&format!("~/.grind/jdks/v{}", version))
+        .ok_or("unable to expand tilde path!")?;
+
+    // the macOS archives nest the actual JDK inside the bundle layout
+    let bundle = home.join("Contents").join("Home");
+    if bundle.exists() {
+        return Ok(bundle);
+    }
+    Ok(home)
+}
+
+pub fn get_java_home() -> Option<PathBuf> {
+    match PINNED_JDK.get() {
+        Some(home) => Some(home.clone()),
+        None => std::env::var("JAVA_HOME").ok().map(PathBuf::from),
+    }
+}
+
+pub fn get_javac_path() -> Option<PathBuf> {
+    // the javac that `command` runs, with any symlinks (e.g ~/.grind/jdks/current) resolved
+    let javac = match PINNED_JDK.get() {
+        Some(home) => home.join("bin").join("javac"),
+        None => std::env::var("PATH")
+            .ok()?
+            .split(':')
+            .map(|dir| Path::new(dir).join("javac"))
+            .find(|path| path.is_file())?,
+    };
+    fs::canonicalize(&javac).ok().or(Some(javac))
+}
+
+pub fn command(tool: &str) -> Command {
+    // e.g `javac`, the pinned JDK's absolute path or whatever is on the PATH otherwise
+    match PINNED_JDK.get() {
+        Some(home) => {
+            let mut command = Command::new(home.join("bin").join(tool));
+            command.env("JAVA_HOME", home);
+            command
+        }
+        None => Command::new(tool),
+    }
+}
+
+pub fn get_task_envs() -> Vec<(String, String)> {
+    // user tasks run through bash, so the pinned JDK goes first on their PATH
+    match PINNED_JDK.get() {
+        Some(home) => {
+            let path = std::env::var("PATH").unwrap_or_default();
+            vec![
+                ("JAVA_HOME".to_string(), home.display().to_string()),
+                (
+                    "PATH".to_string(),
+                    format!("{}:{}", home.join("bin").display(), path),
+                ),
+            ]
+        }
+        None => Vec::new(),
+    }
+}
+
 fn create_jdk_dir() -> Result<(), String> {
     let full_path =
         util::expand_tilde(&format!("~/.grind/jdks")).ok_or("unable to expand tilde path!")?;
@@ -349,7 +513,7 @@ fn map_arch(arch: &str) -> String {
     arch.replace("x86_64", "x64").replace("x86", "x32")
 }
 
-pub fn remove() {
+pub fn remove() -> Result<(), GrindError> {
     /* ---------------------------------------------------------------------------------------------
     nuke the symlink
     remove from path
@@ -359,10 +523,12 @@ pub fn remove() {
         Ok(_) => {
             println!("💣 Grind Managed JDK Destroyed!");
             println!("✔ Updated .bashrc — ⚡ WARNING: restart your terminal");
+            Ok(())
         }
-        Err(e) => {
-            println!("Error, unable to remove Grind managed JDK! {}", e);
-        }
+        Err(e) => Err(GrindError::Other(format!(
+            "❌ Unable to remove Grind managed JDK! {}",
+            e
+        ))),
     }
 }
 
@@ -0,0 +1,203 @@
+use crate::Grind;
+use crate::build;
+use crate::config::Dependency;
+use crate::config::Project;
+use crate::error::GrindError;
+use crate::java;
+use crate::util;
+use quick_xml::escape::escape;
+use std::fs;
+use std::path::Path;
+
+/* -------------------------------------------------------------------------------------------------
+`type: library` builds a jar meant to be used by other projects rather than run, following the Maven
+repository naming so it can be published as is:
+
+    build/
+        - PaymentsApi-1.0.0.jar          no Main-Class or Class-Path, just the Implementation-* and
+                                         Automatic-Module-Name attributes
+        - PaymentsApi-1.0.0-sources.jar  only with `sourcesJar: true`
+        - PaymentsApi-1.0.0.pom          the dependencies (and constraints) from the grind.yml
+------------------------------------------------------------------------------------------------- */
+
+const MANIFEST_FILE: &str = "cache/library-manifest.mf";
+
+pub fn build_library(grind: &Grind) -> Result<(), GrindError> {
+    let project = &grind.project;
+    let base = format!("build/{}-{}", project.artifactId, project.version);
+
+    println!("==> 📚 packaging library [{}]...", project.artifactId);
+
+    let _ = fs::remove_dir_all("build");
+    fs::create_dir_all("build")?;
+    fs::create_dir_all("cache")?;
+
+    fs::write(MANIFEST_FILE, self::get_manifest(grind))
+        .map_err(|e| format!("⚠️ Error: unable to generate the manifest: {}", e))?;
+
+    let status = util::exec(
+        java::command("jar")
+            .arg("cfm")
+            .arg(format!("{}.jar", base))
+            .arg(MANIFEST_FILE)
+            .args(build::get_jar_contents()?),
+    );
+    build::check_status("jar", status)?;
+
+    if project.sourcesJar.unwrap_or(false) {
+        let mut command = java::command("jar");
+        command
+            .arg("cf")
+            .arg(format!("{}-sources.jar", base))
+            .args(["-C", "src/main/java", "."]);
+        if Path::new("src/main/resources").exists() {
+            command.args(["-C", "src/main/resources", "."]);
+        }
+        build::check_status("jar", util::exec(&mut command))?;
+        println!("📦 created {}-sources.jar", base);
+    }
+
+    fs::write(format!("{}.pom", base), self::get_pom(project))?;
+
+    println!("✅ library created: {}.jar", base);
+    Ok(())
+}
+
+fn get_module_name(project: &Project) -> String {
+    // e.g com.example + PaymentsApi => com.example.PaymentsApi, but io.javalin + javalin => io.javalin
+    match project.groupId.rsplit('.').next() {
+        Some(last) if last == project.artifactId => project.groupId.clone(),
+        _ => format!("{}.{}", project.groupId, project.artifactId),
+    }
+}
+
+fn get_manifest(grind: &Grind) -> String {
+    let project = &grind.project;
+    format!(
+        "Implementation-Title: {}\nImplementation-Version: {}\nImplementation-Vendor-Id: {}\nAutomatic-Module-Name: {}{}\n",
+        project.name,
+        project.version,
+        project.groupId,
+        self::get_module_name(project),
+        build::get_release_attributes(grind)
+    )
+}
+
+fn get_pom(project: &Project) -> String {
+    let mut pom = String::new();
+
+    pom.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://maven.apache.org/POM/4.0.0 https://maven.apache.org/xsd/maven-4.0.0.xsd">
+  <modelVersion>4.0.0</modelVersion>
+"#);
+    pom.push_str(&format!(
+        "  <groupId>{}</groupId>\n  <artifactId>{}</artifactId>\n  <version>{}</version>\n  <packaging>jar</packaging>\n  <name>{}</name>\n  <description>{}</description>\n",
+        escape(project.groupId.as_str()),
+        escape(project.artifactId.as_str()),
+        escape(project.version.as_str()),
+        escape(project.name.as_str()),
+        escape(project.description.as_str())
+    ));
+
+    // constraints only pin versions, which is exactly what dependencyManagement is for
+    if let Some(constraints) = project.constraints.as_ref().filter(|c| !c.is_empty()) {
+        pom.push_str("  <dependencyManagement>\n    <dependencies>\n");
+        for dep in constraints {
+            pom.push_str(&self::get_pom_dependency(dep, "      "));
+        }
+        pom.push_str("    </dependencies>\n  </dependencyManagement>\n");
+    }
+
+    if !project.dependencies.is_empty() {
+        pom.push_str("  <dependencies>\n");
+        for dep in &project.dependencies {
+            pom.push_str(&self::get_pom_dependency(dep, "    "));
+        }
+        pom.push_str("  </dependencies>\n");
+    }
+
+    pom.push_str("</project>\n");
+    pom
+}
+
+fn get_pom_dependency(dep: &Dependency, indent: &str) -> String {
+    let mut xml = format!(
+        "{0}<dependency>\n{0}  <groupId>{1}</groupId>\n{0}  <artifactId>{2}</artifactId>\n{0}  <version>{3}</version>\n",
+        indent,
+        escape(dep.groupId.as_str()),
+        escape(dep.artifactId.as_str()),
+        escape(dep.version.as_str())
+    );
+    if let Some(classifier) = &dep.classifier {
+        xml.push_str(&format!(
+            "{}  <classifier>{}</classifier>\n",
+            indent,
+            escape(classifier.as_str())
+        ));
+    }
+    // compile is Maven's default, so it's left out
+    if let Some(scope) = dep.scope.as_deref().filter(|s| *s != "compile") {
+        xml.push_str(&format!("{}  <scope>{}</scope>\n", indent, escape(scope)));
+    }
+    if let Some(exclusions) = dep.exclusions.as_ref().filter(|e| !e.is_empty()) {
+        xml.push_str(&format!("{}  <exclusions>\n", indent));
+        for exclusion in exclusions {
+            // a bare groupId excludes every artifact in it
+            let (group_id, artifact_id) = exclusion.split_once(':').unwrap_or((exclusion, "*"));
+            xml.push_str(&format!(
+                "{0}    <exclusion>\n{0}      <groupId>{1}</groupId>\n{0}      <artifactId>{2}</artifactId>\n{0}    </exclusion>\n",
+                indent,
+                escape(group_id),
+                escape(artifact_id)
+            ));
+        }
+        xml.push_str(&format!("{}  </exclusions>\n", indent));
+    }
+    xml.push_str(&format!("{}</dependency>\n", indent));
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_pom() {
+        let raw = r#"project:
+  groupId: "io.javalin"
+  artifactId: "javalin"
+  version: "6.3.0"
+  name: "Javalin"
+  description: "Simple & lightweight"
+  type: "library"
+  dependencies:
+    - groupId: "org.slf4j"
+      artifactId: "slf4j-api"
+      version: "2.0.16"
+      scope: "compile"
+      exclusions: ["org.example", "org.other:thing"]
+    - groupId: "junit"
+      artifactId: "junit"
+      version: "4.13.2"
+      scope: "test"
+  tasks: {}
+"#;
+        let grind: Grind = serde_yaml::from_str(raw).unwrap();
+        let project = &grind.project;
+
+        assert_eq!(get_module_name(project), "io.javalin");
+
+        let pom = get_pom(project);
+        assert!(pom.contains("<description>Simple &amp; lightweight</description>"));
+        assert!(pom.contains("<artifactId>slf4j-api</artifactId>\n      <version>2.0.16</version>\n      <exclusions>"));
+        assert!(
+            pom.contains("<groupId>org.example</groupId>\n          <artifactId>*</artifactId>")
+        );
+        assert!(
+            pom.contains("<groupId>org.other</groupId>\n          <artifactId>thing</artifactId>")
+        );
+        assert!(pom.contains("<version>4.13.2</version>\n      <scope>test</scope>"));
+        assert!(!pom.contains("dependencyManagement"));
+        assert!(!pom.contains("<scope>compile</scope>"));
+    }
+}
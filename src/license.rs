@@ -0,0 +1,471 @@
+use crate::Grind;
+use crate::config::Dependency;
+use crate::config::LicensePolicy;
+use crate::error::GrindError;
+use crate::lock;
+use crate::pom;
+use crate::pom::PomId;
+use crate::pom::PomLicense;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+/* -------------------------------------------------------------------------------------------------
+POMs declare licenses as free text e.g "The Apache Software License, Version 2.0", which is useless
+for tooling. We map the common names/urls found on Maven Central onto their SPDX identifiers, anything
+we can't recognise is kept as is.
+------------------------------------------------------------------------------------------------- */
+
+const KNOWN_LICENSES: &[(&str, &[&str])] = &[
+    (
+        "Apache-2.0",
+        &[
+            "apache license 2",
+            "apache license, version 2",
+            "apache software license",
+            "apache 2",
+            "apache-2.0",
+            "licenses/license-2.0",
+        ],
+    ),
+    (
+        "MIT",
+        &["mit", "mit license", "the mit license", "licenses/mit"],
+    ),
+    (
+        "BSD-2-Clause",
+        &[
+            "bsd-2-clause",
+            "bsd 2-clause",
+            "simplified bsd",
+            "licenses/bsd-2-clause",
+        ],
+    ),
+    (
+        "BSD-3-Clause",
+        &[
+            "bsd-3-clause",
+            "bsd 3-clause",
+            "new bsd",
+            "revised bsd",
+            "the bsd license",
+            "bsd license",
+            "eclipse distribution license",
+            "edl-v10",
+            "licenses/bsd-3-clause",
+        ],
+    ),
+    (
+        "EPL-1.0",
+        &[
+            "eclipse public license 1.0",
+            "eclipse public license v1.0",
+            "eclipse public license - v 1.0",
+            "epl-v10",
+            "epl-1.0",
+        ],
+    ),
+    (
+        "EPL-2.0",
+        &[
+            "eclipse public license 2.0",
+            "eclipse public license v2.0",
+            "eclipse public license - v 2.0",
+            "epl-2.0",
+            "epl-v20",
+        ],
+    ),
+    (
+        "GPL-2.0-with-classpath-exception",
+        &[
+            "gpl2 w/ cpe",
+            "gpl-2.0-with-classpath-exception",
+            "gnu general public license, version 2 with the classpath exception",
+            "classpath exception",
+        ],
+    ),
+    (
+        "LGPL-2.1",
+        &[
+            "lesser general public license, version 2.1",
+            "lgpl 2.1",
+            "lgpl-2.1",
+            "lgpl, version 2.1",
+        ],
+    ),
+    (
+        "LGPL-3.0",
+        &[
+            "lesser general public license, version 3",
+            "lgpl 3",
+            "lgpl-3.0",
+            "lgpl-3",
+        ],
+    ),
+    (
+        "AGPL-3.0",
+        &["affero general public license", "agpl-3.0", "agpl v3"],
+    ),
+    (
+        "GPL-2.0",
+        &[
+            "general public license, version 2",
+            "gpl-2.0",
+            "gpl v2",
+            "gplv2",
+        ],
+    ),
+    (
+        "GPL-3.0",
+        &[
+            "general public license, version 3",
+            "gpl-3.0",
+            "gpl v3",
+            "gplv3",
+        ],
+    ),
+    (
+        "MPL-2.0",
+        &[
+            "mozilla public license, version 2.0",
+            "mozilla public license 2.0",
+            "mpl 2.0",
+            "mpl-2.0",
+        ],
+    ),
+    (
+        "CDDL-1.1",
+        &[
+            "cddl 1.1",
+            "cddl-1.1",
+            "common development and distribution license (cddl) v1.1",
+        ],
+    ),
+    (
+        "CDDL-1.0",
+        &[
+            "cddl 1.0",
+            "cddl-1.0",
+            "common development and distribution license",
+        ],
+    ),
+    ("CC0-1.0", &["cc0", "creative commons cc0", "cc0-1.0"]),
+];
+
+pub fn get_spdx_id(license: &PomLicense) -> Option<&'static str> {
+    let name = license.name.as_deref().unwrap_or_default().to_lowercase();
+    let url = license.url.as_deref().unwrap_or_default().to_lowercase();
+
+    // NOTE: order matters, e.g the "classpath exception", "lesser" and "affero" variants must be
+    // checked before the plain GPL. Very short needles e.g "mit" must be an exact match.
+    for (id, needles) in KNOWN_LICENSES {
+        if needles.iter().any(|needle| {
+            name == *needle || (needle.len() > 3 && (name.contains(needle) || url.contains(needle)))
+        }) {
+            return Some(id);
+        }
+    }
+    None
+}
+
+pub fn get_display_name(license: &PomLicense) -> String {
+    match self::get_spdx_id(license) {
+        Some(id) => id.to_string(),
+        None => license
+            .name
+            .clone()
+            .or(license.url.clone())
+            .unwrap_or("Unknown".to_string()),
+    }
+}
+
+pub async fn get_licenses(deps: &[Dependency]) -> HashMap<Dependency, Vec<PomLicense>> {
+    let mut licenses = HashMap::new();
+
+    for dep in deps {
+        let pom_id = PomId {
+            group_id: dep.groupId.clone(),
+            artifact_id: dep.artifactId.clone(),
+            version: dep.version.clone(),
+        };
+        licenses.insert(dep.clone(), pom::get_licenses(&pom_id).await);
+    }
+    licenses
+}
+
+pub async fn execute_licenses(grind: Grind) -> Result<(), GrindError> {
+    let locked = match lock::get_lock_file() {
+        Ok(locked) => locked,
+        Err(e) => {
+            return Err(GrindError::Resolution(format!(
+                "❌ Unable to read grind.lock, try `grind install` first: {}",
+                e
+            )));
+        }
+    };
+
+    let licenses = self::get_licenses(&locked.lockedDeps).await;
+
+    let mut grouped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for dep in &locked.lockedDeps {
+        let coordinates = format!("{}:{}:{}", dep.groupId, dep.artifactId, dep.version);
+        let names = match licenses.get(dep) {
+            Some(found) if !found.is_empty() => found.iter().map(self::get_display_name).collect(),
+            _ => vec!["Unknown".to_string()],
+        };
+        for name in names {
+            grouped.entry(name).or_default().push(coordinates.clone());
+        }
+    }
+
+    println!();
+    for (name, mut deps) in grouped {
+        deps.sort();
+        println!("📜 {} ({})", name, deps.len());
+        for dep in deps {
+            println!("   - {}", dep);
+        }
+        println!();
+    }
+
+    if let Some(policy) = &grind.project.licensePolicy {
+        let violations = self::get_violations(policy, &licenses);
+        self::print_violations(&violations);
+
+        if !violations.is_empty() {
+            return Err(GrindError::Resolution(
+                "❌ disallowed licenses found!".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub async fn check_policy(grind: &Grind, deps: &[Dependency]) -> bool {
+    /* ---------------------------------------------------------------------------------------------
+    licensePolicy:
+      allow: ["Apache-2.0", "MIT", "BSD-*"]
+      deny: ["GPL-*", "AGPL-*"]
+
+    several licenses in a POM are a choice (dual licensing) e.g "EPL-2.0 OR GPL-2.0-with-CPE", so a
+    dependency is only rejected when *every* license is denied, if an allow list is given then at
+    least one of the licenses that isn't denied must be allowed. "Unknown" is never allowed.
+    --------------------------------------------------------------------------------------------- */
+    let policy = match &grind.project.licensePolicy {
+        Some(policy) => policy,
+        None => return true,
+    };
+
+    println!("📜 checking dependency licenses against the licensePolicy...");
+    let licenses = self::get_licenses(deps).await;
+    let violations = self::get_violations(policy, &licenses);
+    self::print_violations(&violations);
+
+    violations.is_empty()
+}
+
+fn get_violations(
+    policy: &LicensePolicy,
+    licenses: &HashMap<Dependency, Vec<PomLicense>>,
+) -> Vec<(Dependency, String)> {
+    let mut violations = Vec::new();
+
+    for (dep, found) in licenses {
+        let names = found
+            .iter()
+            .map(|l| {
+                (
+                    self::get_display_name(l),
+                    l.name.clone().unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let (denied, usable): (Vec<_>, Vec<_>) = names.iter().partition(|(id, raw)| {
+            policy
+                .deny
+                .iter()
+                .any(|p| self::is_match(p, id) || self::is_match(p, raw))
+        });
+
+        if !denied.is_empty() && usable.is_empty() {
+            let denied = denied
+                .iter()
+                .map(|(id, _)| id.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            violations.push((dep.clone(), format!("{} is denied", denied)));
+            continue;
+        }
+
+        if !policy.allow.is_empty()
+            && !usable.iter().any(|(id, raw)| {
+                policy
+                    .allow
+                    .iter()
+                    .any(|p| self::is_match(p, id) || self::is_match(p, raw))
+            })
+        {
+            let reason = if names.is_empty() {
+                "Unknown license is not in the allow list".to_string()
+            } else {
+                format!(
+                    "{} not in the allow list",
+                    names
+                        .iter()
+                        .map(|(id, _)| id.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+            violations.push((dep.clone(), reason));
+        }
+    }
+
+    violations.sort_by(|a, b| {
+        a.0.groupId
+            .cmp(&b.0.groupId)
+            .then(a.0.artifactId.cmp(&b.0.artifactId))
+    });
+    violations
+}
+
+fn print_violations(violations: &[(Dependency, String)]) {
+    if violations.is_empty() {
+        println!("✅ All dependency licenses comply with the licensePolicy.");
+        return;
+    }
+
+    println!("❌ {} licensePolicy violation(s):", violations.len());
+    for (dep, reason) in violations {
+        println!(
+            "   - {}:{}:{} | {}",
+            dep.groupId, dep.artifactId, dep.version, reason
+        );
+    }
+}
+
+fn is_match(pattern: &str, license: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let license = license.to_lowercase();
+
+    if license.is_empty() {
+        return false;
+    }
+
+    match pattern.strip_suffix('*') {
+        Some(prefix) => license.starts_with(prefix),
+        None => license == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spdx_mapping() {
+        let cases = vec![
+            (
+                "The Apache Software License, Version 2.0",
+                "",
+                Some("Apache-2.0"),
+            ),
+            (
+                "",
+                "https://www.apache.org/licenses/LICENSE-2.0.txt",
+                Some("Apache-2.0"),
+            ),
+            ("Eclipse Public License 1.0", "", Some("EPL-1.0")),
+            ("GPL2 w/ CPE", "", Some("GPL-2.0-with-classpath-exception")),
+            (
+                "GNU Lesser General Public License, Version 2.1",
+                "",
+                Some("LGPL-2.1"),
+            ),
+            ("GNU General Public License, version 3", "", Some("GPL-3.0")),
+            (
+                "GNU Affero General Public License, version 3",
+                "",
+                Some("AGPL-3.0"),
+            ),
+            ("MIT", "", Some("MIT")),
+            ("Some Custom License", "", None),
+        ];
+
+        for (name, url, expected) in cases {
+            let license = PomLicense {
+                name: Some(name.to_string()),
+                url: Some(url.to_string()),
+            };
+            assert_eq!(get_spdx_id(&license), expected, "license '{}'", name);
+        }
+    }
+
+    #[test]
+    fn test_policy_violations() {
+        let policy = LicensePolicy {
+            allow: vec!["Apache-2.0".to_string(), "MIT".to_string()],
+            deny: vec!["GPL-*".to_string()],
+        };
+
+        let dep = |artifact: &str| Dependency {
+            groupId: "com.example".to_string(),
+            artifactId: artifact.to_string(),
+            version: "1.0".to_string(),
+            scope: None,
+            ..Default::default()
+        };
+        let license = |name: &str| PomLicense {
+            name: Some(name.to_string()),
+            url: None,
+        };
+
+        let mut licenses = HashMap::new();
+        licenses.insert(
+            dep("apache"),
+            vec![license("The Apache Software License, Version 2.0")],
+        );
+        licenses.insert(dep("dual"), vec![license("MIT"), license("EPL-2.0")]);
+        licenses.insert(
+            dep("gpl"),
+            vec![license("GNU General Public License, version 3")],
+        );
+        licenses.insert(dep("epl"), vec![license("Eclipse Public License 1.0")]);
+        // one of the options is denied, but the other one is allowed
+        licenses.insert(
+            dep("dual-gpl"),
+            vec![
+                license("GNU General Public License, version 3"),
+                license("MIT"),
+            ],
+        );
+        // every option is either denied or not allowed
+        licenses.insert(
+            dep("gpl-or-epl"),
+            vec![
+                license("GNU General Public License, version 3"),
+                license("Eclipse Public License 1.0"),
+            ],
+        );
+        licenses.insert(dep("unknown"), vec![]);
+
+        let violations = get_violations(&policy, &licenses);
+        let rejected = violations
+            .iter()
+            .map(|(dep, _)| dep.artifactId.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(rejected, vec!["epl", "gpl", "gpl-or-epl", "unknown"]);
+
+        // without an allow list, only all denied options are rejected
+        let policy = LicensePolicy {
+            allow: Vec::new(),
+            deny: vec!["GPL-*".to_string()],
+        };
+        let rejected = get_violations(&policy, &licenses)
+            .into_iter()
+            .map(|(dep, _)| dep.artifactId)
+            .collect::<Vec<_>>();
+        assert_eq!(rejected, vec!["gpl"]);
+    }
+}
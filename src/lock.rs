@@ -7,7 +7,20 @@ use std::fs;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Lock {
     pub inputDeps: Vec<Dependency>,
+    #[serde(default)]
+    pub inputConstraints: Vec<Dependency>,
     pub lockedDeps: Vec<Dependency>,
+    #[serde(default)]
+    pub forcedDeps: Vec<Dependency>,
+    // annotation processors are resolved as their own graph, they're never on the classpath
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputProcessors: Vec<Dependency>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lockedProcessors: Vec<Dependency>,
+    // the deps found with more than one version before any collisions were fixed, kept for the
+    // `dependencyConvergence` rule (None for a grind.lock written before it was recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub divergentDeps: Option<Vec<Dependency>>,
 }
 
 pub fn get_lock_file() -> Result<Lock, String> {
@@ -16,13 +29,8 @@ pub fn get_lock_file() -> Result<Lock, String> {
     Ok(parsed)
 }
 
-pub fn lock_file(input_deps: &Vec<Dependency>, locked_deps: &Vec<Dependency>) {
-    let lock = Lock {
-        inputDeps: input_deps.to_vec(),
-        lockedDeps: locked_deps.to_vec(),
-    };
-
-    if let Ok(updated) = serde_yaml::to_string(&lock) {
+pub fn lock_file(lock: &Lock) {
+    if let Ok(updated) = serde_yaml::to_string(lock) {
         if fs::write("grind.lock", updated).is_ok() {
             println!("🔃 grind.lock synced..");
         }
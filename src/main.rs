@@ -1,30 +1,49 @@
 use clap::{Parser, Subcommand};
+use std::fs;
 use std::fs::File;
 use std::io::Read;
 use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 
+mod audit;
 mod build;
 mod config;
+mod editor;
+mod error;
+mod incremental;
 mod install;
 mod integrity;
 mod java;
+mod library;
+mod license;
 mod lock;
 mod manage;
+mod manifest;
 mod metadata;
 mod mock;
 mod pom;
+mod resources;
+mod rules;
 mod run;
+mod sbom;
 mod scaffold;
+mod schema;
+mod search;
+mod signature;
 mod tasks;
 mod tests;
 mod uberjar;
 mod util;
+mod validate;
 
+use crate::audit::Severity;
 use crate::build::BuildTarget;
 use crate::config::Grind;
-use crate::util::shell;
+use crate::config::Profile;
+use crate::error::GrindError;
+use crate::manage::AddOptions;
+use crate::sbom::SbomFormat;
 
 const LOGO: &str = r#"
                      /$$                 /$$
@@ -65,21 +84,71 @@ enum Commands {
         name: String,
     },
     /// Download all the external libraries and dependencies as defined in the grind.yml
-    Install,
+    Install {
+        /// also download the -sources.jar of every dependency (for editors)
+        #[arg(long)]
+        sources: bool,
+        /// also download the -javadoc.jar of every dependency (for editors)
+        #[arg(long)]
+        javadoc: bool,
+    },
     /// Compile the project and builds a jar file.
     Build {
-        /// the defined profile to build with, these include compiler flags, and environment variables
+        /// the profile(s) to build with, as defined in the grind.yml `profiles` (can be repeated,
+        /// merged in order)
+        #[arg(long, short)]
         profile: Vec<String>,
+        /// the entrypoint to use as the jar's Main-Class, as defined in the grind.yml `entrypoints`
+        #[arg(long)]
+        bin: Option<String>,
     },
     /// Compile and run the project
     Run {
-        /// the defined profile to run with, these include compiler flags, and environment variables
+        /// the profile(s) to run with, as defined in the grind.yml `profiles` (can be repeated,
+        /// merged in order)
+        #[arg(long, short)]
         profile: Vec<String>,
+        /// the entrypoint to run, as defined in the grind.yml `entrypoints`
+        #[arg(long)]
+        bin: Option<String>,
+        /// arguments passed through to the program, after a `--` e.g grind run -- --port 8080
+        #[arg(last = true)]
+        args: Vec<String>,
     },
     /// Adds a dependency to the project's grind.yml
     Add {
-        /// List of dependencies to add (e.g., 'io.javalin/javalin org.posgresql/postgresql')
+        /// List of dependencies to add (e.g., 'io.javalin/javalin org.posgresql/postgresql'), the
+        /// groupId can be left out e.g 'jackson-databind' to search for it
         deps: Vec<String>,
+        /// don't ask, automatically pick the top search result
+        #[arg(long, short)]
+        yes: bool,
+        /// only needed to compile and run the tests e.g junit
+        #[arg(long, conflicts_with_all = ["runtime", "provided"])]
+        test: bool,
+        /// only needed at runtime e.g a JDBC driver
+        #[arg(long, conflicts_with = "provided")]
+        runtime: bool,
+        /// needed to compile, but supplied at runtime e.g servlet-api, left out of the jar/bundle
+        #[arg(long)]
+        provided: bool,
+        /// the artifact classifier e.g natives-linux
+        #[arg(long)]
+        classifier: Option<String>,
+        /// exclude a transitive dependency, <groupId>[:<artifactId>] (can be repeated)
+        #[arg(long)]
+        exclude: Vec<String>,
+    },
+    /// Search for artifacts by name, e.g grind search jackson-databind
+    Search {
+        /// the term to search for, either a name or <GroupId>/<ArtifactId>
+        term: String,
+        /// maximum number of results to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// search a local index file instead of Maven Central
+        #[arg(long)]
+        index: Option<PathBuf>,
     },
     /// Removes a dependency from the project's grind.yml
     Remove {
@@ -97,8 +166,41 @@ enum Commands {
     Test { tests: Vec<String> },
     /// Packages compiled classes and all dependency jars into a single runnable JAR, also known as a "Fat Jar" or "Uberjar"
     Bundle {
-        /// the defined profile to run with, these include compiler flags, and environment variables
+        /// the profile(s) to build with, as defined in the grind.yml `profiles` (can be repeated,
+        /// merged in order)
+        #[arg(long, short)]
         profile: Vec<String>,
+        /// the entrypoint to use as the jar's Main-Class, as defined in the grind.yml `entrypoints`
+        #[arg(long)]
+        bin: Option<String>,
+    },
+    /// List the licenses of every locked dependency, grouped by license
+    Licenses,
+    /// Validate the grind.yml, reporting any errors and warnings
+    CheckConfig,
+    /// Print the JSON Schema of the grind.yml, for editor autocomplete and validation
+    Schema {
+        /// write it to a file instead e.g grind.schema.json
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Audit every locked dependency against an offline OSV vulnerability database
+    Audit {
+        /// path to an OSV advisory file or directory, defaults to ~/.grind/advisories
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// exit with a failure when a vulnerability at or above this severity is found
+        #[arg(long, value_enum, default_value = "low")]
+        fail_on: Severity,
+    },
+    /// Generate a Software Bill of Materials (SBOM) from the grind.lock, works offline
+    Sbom {
+        /// the SBOM format to generate
+        #[arg(long, value_enum, default_value = "cyclonedx-json")]
+        format: SbomFormat,
+        /// where to write the SBOM, defaults to sbom.cdx.json or sbom.spdx.json
+        #[arg(long, short)]
+        output: Option<PathBuf>,
     },
     /// Manage Java Versions
     Java {
@@ -140,107 +242,157 @@ enum IntegritySubcommand {
 async fn main() {
     let cli = Cli::parse();
 
-    match cli.command {
+    let result = match cli.command {
         Commands::New { name } => self::handle_new(&name),
-        Commands::Build { profile } => self::handle_build(profile),
-        Commands::Install => self::handle_install().await,
-        Commands::Run { profile } => self::handle_run(profile),
-        Commands::Add { deps } => self::handle_add(deps).await,
+        Commands::Build { profile, bin } => self::handle_build(profile, bin).await,
+        Commands::Install { sources, javadoc } => self::handle_install(sources, javadoc).await,
+        Commands::Run { profile, bin, args } => self::handle_run(profile, bin, args).await,
+        Commands::Add {
+            deps,
+            yes,
+            test,
+            runtime,
+            provided,
+            classifier,
+            exclude,
+        } => {
+            let scope = match (test, runtime, provided) {
+                (true, _, _) => "test",
+                (_, true, _) => "runtime",
+                (_, _, true) => "provided",
+                _ => "compile",
+            };
+            let options = AddOptions {
+                yes,
+                scope: scope.to_string(),
+                classifier,
+                exclusions: exclude,
+            };
+            self::handle_add(deps, options).await
+        }
+        Commands::Search { term, limit, index } => self::handle_search(term, limit, index).await,
         Commands::Remove { deps } => self::handle_remove(deps).await,
-        Commands::Task { job } => self::handle_task(job),
+        Commands::Task { job } => self::handle_task(job).await,
         Commands::Integrity { integrity } => match integrity {
-            IntegritySubcommand::Generate { dir } => {
-                let _ = self::handle_generate_integrity(dir);
-            }
-            IntegritySubcommand::Validate { dir } => {
-                let _ = self::handle_validate_integrity(dir);
-            }
+            IntegritySubcommand::Generate { dir } => self::handle_generate_integrity(dir),
+            IntegritySubcommand::Validate { dir } => self::handle_validate_integrity(dir),
         },
         Commands::Test { tests } => self::handle_tests(tests).await,
-        Commands::Bundle { profile } => self::handle_bundle(profile),
+        Commands::Bundle { profile, bin } => self::handle_bundle(profile, bin).await,
+        Commands::Licenses => self::handle_licenses().await,
+        Commands::CheckConfig => validate::execute_check_config(),
+        Commands::Schema { output } => schema::execute_schema(output),
+        Commands::Audit { db, fail_on } => audit::execute_audit(db, fail_on),
+        Commands::Sbom { format, output } => self::handle_sbom(format, output).await,
         Commands::Java { java } => match java {
             JavaVersionManger::List => java::list().await,
             JavaVersionManger::Current => java::current(),
             JavaVersionManger::Use { version } => java::_use(version).await,
             JavaVersionManger::Remove => java::remove(),
         },
+    };
+
+    if let Err(e) = result {
+        println!("{}", e);
+        std::process::exit(e.exit_code());
     }
 }
 
-fn handle_new(name: &str) {
+fn handle_new(name: &str) -> Result<(), GrindError> {
     match self::parse_project_name(name) {
         Ok((namespace, artifact_id)) => {
             let folder_path = Path::new(artifact_id);
 
             if !folder_path.exists() || !folder_path.is_dir() {
                 scaffold::create(namespace, artifact_id);
+                Ok(())
             } else {
-                println!(
+                Err(GrindError::Other(format!(
                     "⚠️ Sorry project folder '{}' already exists, exiting...",
                     artifact_id
-                );
+                )))
             }
         }
-        Err(e) => {
-            println!(
-                "⚠️ Sorry '{}' is not a valid project name, requires a namespace and artifactId, e.g com.example/HelloWorld",
-                name
-            );
-            println!("{e}");
-        }
+        Err(e) => Err(GrindError::Other(format!(
+            "⚠️ Sorry '{}' is not a valid project name, requires a namespace and artifactId, e.g com.example/HelloWorld\n{}",
+            name, e
+        ))),
     }
 }
 
-fn handle_build(profile: Vec<String>) {
-    if let Some(grind) = util::parse_grind_file() {
-        let args = self::get_run_args(&grind, profile);
+async fn load_project() -> Result<Grind, GrindError> {
+    // anything that runs javac, java or jar must use the project's pinned JDK (if any)
+    let grind = util::parse_grind_file()?;
+    java::use_pinned_jdk(&grind)
+        .await
+        .map_err(GrindError::Resolution)?;
+    Ok(grind)
+}
 
-        build::execute_build(&grind, BuildTarget::IncludeJar, args.flags);
-    } else {
-        println!("⚠️ Error: no grind.yml or invalid grind.yml")
-    }
+async fn handle_build(profile: Vec<String>, bin: Option<String>) -> Result<(), GrindError> {
+    let grind = self::load_project().await?;
+    let args = RunArgs {
+        bin,
+        ..self::get_run_args(&grind, &profile, Vec::new())?
+    };
+
+    build::execute_build(&grind, BuildTarget::IncludeJar, &args)
 }
 
-async fn handle_install() {
-    if let Some(grind) = util::parse_grind_file() {
-        install::execute_install(grind).await;
-    } else {
-        println!("⚠️ Error: no grind.yml or invalid grind.yml")
+async fn handle_install(sources: bool, javadoc: bool) -> Result<(), GrindError> {
+    let mut grind = self::load_project().await?;
+
+    // the command line flags simply switch on the equivalent grind.yml settings
+    if sources {
+        grind.project.downloadSources = Some(true);
     }
+    if javadoc {
+        grind.project.downloadJavadoc = Some(true);
+    }
+    install::execute_install(grind).await
 }
 
-fn handle_run(profile: Vec<String>) {
-    if let Some(grind) = util::parse_grind_file() {
-        let args = self::get_run_args(&grind, profile);
+async fn handle_run(
+    profile: Vec<String>,
+    bin: Option<String>,
+    args: Vec<String>,
+) -> Result<(), GrindError> {
+    let grind = self::load_project().await?;
+    let args = RunArgs {
+        bin,
+        ..self::get_run_args(&grind, &profile, args)?
+    };
 
-        run::execute_run(grind, &args);
-    } else {
-        println!("⚠️ Error: no grind.yml or invalid grind.yml")
-    }
+    run::execute_run(grind, &args)
 }
 
-fn handle_task(job: String) {
-    if let Some(grind) = util::parse_grind_file() {
-        tasks::execute_task(grind, job);
-    } else {
-        println!("⚠️ Error: no grind.yml or invalid grind.yml")
-    }
+async fn handle_task(job: String) -> Result<(), GrindError> {
+    let grind = self::load_project().await?;
+    tasks::execute_task(grind, job)
 }
 
-async fn handle_add(deps: Vec<String>) {
-    if let Some(grind) = util::parse_grind_file() {
-        manage::execute_add(grind, deps).await;
-    } else {
-        println!("⚠️ Error: no grind.yml or invalid grind.yml")
-    }
+async fn handle_add(deps: Vec<String>, options: AddOptions) -> Result<(), GrindError> {
+    let grind = util::parse_grind_file()?;
+    manage::execute_add(grind, deps, options).await
 }
 
-async fn handle_remove(deps: Vec<String>) {
-    if let Some(grind) = util::parse_grind_file() {
-        manage::execute_remove(grind, deps).await;
+async fn handle_search(
+    term: String,
+    limit: usize,
+    index: Option<PathBuf>,
+) -> Result<(), GrindError> {
+    // searching doesn't require a project, but we honour its `searchIndex` if there is one
+    let grind = if Path::new("grind.yml").exists() {
+        util::parse_grind_file().map_err(|e| println!("{}", e)).ok()
     } else {
-        println!("⚠️ Error: no grind.yml or invalid grind.yml")
-    }
+        None
+    };
+    search::execute_search(grind, term, limit, index).await
+}
+
+async fn handle_remove(deps: Vec<String>) -> Result<(), GrindError> {
+    let grind = util::parse_grind_file()?;
+    manage::execute_remove(grind, deps).await
 }
 
 fn parse_project_name(input: &str) -> Result<(&str, &str), &'static str> {
@@ -280,7 +432,7 @@ fn is_valid_java_identifier(identifier: &str) -> bool {
     true
 }
 
-fn validate_namespace(namespace: &str) -> Result<&str, &'static str> {
+pub fn validate_namespace(namespace: &str) -> Result<&str, &'static str> {
     for part in namespace.split('.') {
         match is_valid_java_identifier(part) {
             true => continue,
@@ -291,141 +443,173 @@ fn validate_namespace(namespace: &str) -> Result<&str, &'static str> {
     Ok(namespace)
 }
 
-fn validate_artifact_id(artifact_id: &str) -> Result<&str, &'static str> {
+pub fn validate_artifact_id(artifact_id: &str) -> Result<&str, &'static str> {
     match is_valid_java_identifier(artifact_id) {
         true => Ok(artifact_id),
         false => Err("⚠️ Your artifactId contains an invalid java identifier"),
     }
 }
 
-fn handle_generate_integrity(dir: PathBuf) -> Result<(), String> {
-    let json = integrity::generate_integrity_data(&dir).map_err(|e| e.to_string())?;
+fn handle_generate_integrity(dir: PathBuf) -> Result<(), GrindError> {
+    let json = integrity::generate_integrity_data(&dir)?;
     let integrity_file = dir.join("integrity.json");
-    let mut file = File::create(&integrity_file).map_err(|e| e.to_string())?;
-    file.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+    let mut file = File::create(&integrity_file)?;
+    file.write_all(json.as_bytes())?;
     println!("✅ Integrity data written to {}", integrity_file.display());
     Ok(())
 }
 
-pub fn handle_validate_integrity(dir: PathBuf) -> Result<(), String> {
+pub fn handle_validate_integrity(dir: PathBuf) -> Result<(), GrindError> {
     let integrity_file = dir.join("integrity.json");
     if !integrity_file.exists() {
-        eprintln!("❌ integrity.json not found in {}", dir.display());
-        return Err("missng integrity.json".to_string());
+        return Err(GrindError::Integrity(format!(
+            "❌ integrity.json not found in {}",
+            dir.display()
+        )));
     }
 
     let mut json_data = String::new();
-    File::open(&integrity_file)
-        .map_err(|e| e.to_string())?
-        .read_to_string(&mut json_data)
-        .map_err(|e| e.to_string())?;
-
-    let valid = integrity::verify_integrity_data(&dir, &json_data).map_err(|e| e.to_string())?;
-    if valid {
-        println!("✅ Integrity check passed.");
-    } else {
-        println!("❌ Integrity check failed.");
-        return Err("integrity check failed".to_string());
+    File::open(&integrity_file)?.read_to_string(&mut json_data)?;
+
+    let valid = integrity::verify_integrity_data(&dir, &json_data)
+        .map_err(|e| GrindError::Integrity(format!("❌ {}", e)))?;
+    if !valid {
+        return Err(GrindError::Integrity(
+            "❌ Integrity check failed.".to_string(),
+        ));
     }
+    println!("✅ Integrity check passed.");
     Ok(())
 }
 
-async fn handle_tests(tests: Vec<String>) {
-    if let Some(grind) = util::parse_grind_file() {
-        tests::run_tests(grind, tests).await;
-    } else {
-        println!("⚠️ Error: no grind.yml or invalid grind.yml")
-    }
+async fn handle_tests(tests: Vec<String>) -> Result<(), GrindError> {
+    let grind = self::load_project().await?;
+    tests::run_tests(grind, tests).await
 }
 
-fn handle_bundle(profile: Vec<String>) {
-    if let Some(grind) = util::parse_grind_file() {
-        // need to compile classes first
-
-        let args = self::get_run_args(&grind, profile);
-
-        build::execute_build(&grind, BuildTarget::BuildOnly, args.flags);
-
-        shell("rm -rf build/ && mkdir build");
+async fn handle_licenses() -> Result<(), GrindError> {
+    let grind = util::parse_grind_file()?;
+    license::execute_licenses(grind).await
+}
 
-        let _ = uberjar::build_fat_jar(&uberjar::FatJarConfig {
-            output_jar: Path::new(&format!("build/{}.jar", grind.project.artifactId)),
-            classes_dir: Path::new("target"),
-            libs_dir: Path::new("libs"),
-            group_id: &grind.project.groupId,
-            artifact_id: &grind.project.artifactId,
-            main_class: &format!("{}.{}", &grind.project.groupId, &grind.project.artifactId),
-        });
-    } else {
-        println!("⚠️ Error: no grind.yml or invalid grind.yml")
-    }
+async fn handle_sbom(format: SbomFormat, output: Option<PathBuf>) -> Result<(), GrindError> {
+    let grind = util::parse_grind_file()?;
+    sbom::execute_sbom(grind, format, output).await
 }
 
-pub fn get_flags(grind: &Grind, profile: String) -> String {
-    let mut flags = String::new();
+async fn handle_bundle(profile: Vec<String>, bin: Option<String>) -> Result<(), GrindError> {
+    let grind = self::load_project().await?;
+
+    // need to compile classes first
+    let args = self::get_run_args(&grind, &profile, Vec::new())?;
+
+    build::execute_build(&grind, BuildTarget::BuildOnly, &args)?;
+    let main_class = build::get_main_class(&grind, bin.as_deref())?;
+
+    let _ = fs::remove_dir_all("build");
+    fs::create_dir_all("build").map_err(|e| format!("❌ Unable to create build/: {}", e))?;
+
+    uberjar::build_fat_jar(&uberjar::FatJarConfig {
+        output_jar: Path::new(&format!("build/{}.jar", grind.project.artifactId)),
+        classes_dir: Path::new("target"),
+        libs_dir: Path::new("libs"),
+        excluded_jars: &install::get_provided_jars(),
+        group_id: &grind.project.groupId,
+        artifact_id: &grind.project.artifactId,
+        main_class: &main_class,
+        attributes: &build::get_release_attributes(&grind),
+    })
+    .map_err(|e| GrindError::Compilation(format!("❌ Unable to build the fat jar: {}", e)))
+}
 
-    if let Some(profiles) = &grind.project.profiles {
-        if let Some(matched) = profiles.get(&profile) {
-            if let Some(matched_flags) = &matched.flags {
-                flags = matched_flags.join(" ")
-            }
-        }
-    }
-    flags
+pub fn get_flags(profile: &Profile) -> Vec<String> {
+    // an entry can hold more than one flag e.g "-source 17"
+    profile
+        .flags
+        .iter()
+        .flatten()
+        .flat_map(|flag| util::split_args(flag))
+        .collect()
 }
 
+#[derive(Default)]
 struct RunArgs {
-    flags: String,
-    envs: String,
+    bin: Option<String>,
+    flags: Vec<String>,
+    envs: Vec<(String, String)>,
+    // resource folders layered over src/main/resources
+    resources: Vec<String>,
     args: Vec<String>,
 }
 
-fn get_run_args(grind: &Grind, args: Vec<String>) -> RunArgs {
+fn get_run_args(
+    grind: &Grind,
+    profiles: &[String],
+    args: Vec<String>,
+) -> Result<RunArgs, GrindError> {
     /* ---------------------------------------------------------------------------------------------
-    because we have a list of arguments provided, there is some overlap in the sense the first
-    argument is ambiguous, because it could mean the "profile" OR it could just be the first normal
-    argument. This function takes the first argument and sees if any profiles match. If there is a
-    match we simply "consume" it from the list and return the flags + envs + args e.g:
+    the profiles picked with `--profile/-p` (or the `defaultProfile:` when there are none) are
+    merged in order, so `-p dev -p local` is the dev profile with the local one on top:
 
-    -> profile:
-            \_ flags
-            \_ envs
+    -> flags        appended
+    -> envs         the later profile's value wins
+    -> resources    appended, i.e the later profile's folders are layered last
 
-    -> rest of args
+    the program's own arguments always come after a `--`, so they can never be mistaken for a
+    profile.
     --------------------------------------------------------------------------------------------- */
-    let mut xargs = args.clone();
-    let mut flags = String::new();
-    let mut envs = String::new();
+    let defaults: Vec<String> = grind.project.defaultProfile.iter().cloned().collect();
+    let names = if profiles.is_empty() {
+        &defaults
+    } else {
+        profiles
+    };
 
-    if let Some(profile) = xargs.first() {
-        flags = self::get_flags(&grind, profile.to_string());
-        envs = self::get_envs(&grind, profile.to_string());
+    let mut run_args = RunArgs {
+        args,
+        ..RunArgs::default()
+    };
 
-        if !flags.is_empty() || !envs.is_empty() {
-            xargs.remove(0);
+    for name in names {
+        let Some(profile) = grind.project.profiles.as_ref().and_then(|p| p.get(name)) else {
+            let mut available: Vec<&str> = grind
+                .project
+                .profiles
+                .iter()
+                .flatten()
+                .map(|(key, _)| key.as_str())
+                .collect();
+            available.sort();
+            return Err(GrindError::Config(format!(
+                "❌ unknown profile '{}', the grind.yml defines: [{}]",
+                name,
+                available.join(", ")
+            )));
+        };
+        println!("🎛️ using profile [{}]", name);
+
+        run_args.flags.extend(self::get_flags(profile));
+
+        for (key, value) in self::get_envs(profile) {
+            run_args.envs.retain(|(existing, _)| *existing != key);
+            run_args.envs.push((key, value));
         }
-    }
 
-    RunArgs {
-        flags: flags,
-        envs: envs,
-        args: xargs,
+        run_args
+            .resources
+            .extend(profile.resources.iter().flatten().cloned());
     }
+
+    Ok(run_args)
 }
 
-pub fn get_envs(grind: &Grind, profile: String) -> String {
-    let mut envs = String::new();
-
-    if let Some(profiles) = &grind.project.profiles {
-        if let Some(matched) = profiles.get(&profile) {
-            if let Some(matched_envs) = &matched.envs {
-                envs = matched_envs
-                    .iter()
-                    .map(|(key, value)| format!("{}={}", key, value))
-                    .collect::<Vec<_>>()
-                    .join(" ");
-            }
-        }
-    }
+pub fn get_envs(profile: &Profile) -> Vec<(String, String)> {
+    let mut envs: Vec<(String, String)> = profile
+        .envs
+        .iter()
+        .flatten()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    envs.sort();
     envs
 }
@@ -1,66 +1,355 @@
 use crate::Grind;
 use crate::config::Dependency;
+use crate::error::GrindError;
 use crate::install;
+use crate::lock;
+use crate::lock::Lock;
+use crate::manifest;
 use crate::metadata;
-use std::fs;
+use crate::search;
+use crate::search::ArtifactIndex;
+use crate::search::SearchResult;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::io;
+use std::io::IsTerminal;
+use std::io::Write;
+
+pub struct AddOptions {
+    pub yes: bool,
+    pub scope: String,
+    pub classifier: Option<String>,
+    pub exclusions: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct DepSpec {
+    group_id: Option<String>,
+    artifact: String,
+    version: Option<String>,
+}
+
+fn parse_dep_spec(input: &str) -> Result<DepSpec, String> {
+    /* ---------------------------------------------------------------------------------------------
+    accepts any of the following:
+
+        <groupId>/<artifactId>
+        <groupId>/<artifactId>@<version>
+        <artifactId>                        (searched for)
+        <artifactId>@<version>              (searched for)
+    --------------------------------------------------------------------------------------------- */
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("⚠️ empty dependency, expected <groupId>/<artifactId>[@version]".to_string());
+    }
+
+    let (coordinates, version) = match input.split_once('@') {
+        Some((_, "")) => {
+            return Err(format!(
+                "⚠️ '{}' is missing a version after the '@', e.g {}42.7.7",
+                input, input
+            ));
+        }
+        Some((c, v)) => (c, Some(v.to_string())),
+        None => (input, None),
+    };
+
+    let is_valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+    };
+
+    let tokens: Vec<&str> = coordinates.split('/').collect();
+    let (group_id, artifact) = match tokens.as_slice() {
+        [artifact] => (None, *artifact),
+        [group_id, artifact] => (Some(group_id.to_string()), *artifact),
+        _ => {
+            return Err(format!(
+                "⚠️ '{}' must contain at most one '/', expected <groupId>/<artifactId>",
+                input
+            ));
+        }
+    };
+
+    if let Some(g) = &group_id
+        && !is_valid(g)
+    {
+        return Err(format!("⚠️ '{}' has an invalid groupId '{}'", input, g));
+    }
+    if !is_valid(artifact) {
+        return Err(format!(
+            "⚠️ '{}' has an invalid artifactId '{}'",
+            input, artifact
+        ));
+    }
+
+    Ok(DepSpec {
+        group_id,
+        artifact: artifact.to_string(),
+        version,
+    })
+}
+
+pub async fn execute_add(
+    grind: Grind,
+    deps: Vec<String>,
+    options: AddOptions,
+) -> Result<(), GrindError> {
+    self::validate_options(&options)?;
 
-pub async fn execute_add(grind: Grind, deps: Vec<String>) {
     let mut candidates = Vec::new();
+    // the rest are still added, but the command as a whole has failed
+    let mut failed = Vec::new();
 
     for dep in deps {
-        let mut group_id = String::new();
-        let mut version = String::new();
-        let mut artifact = String::new();
-
-        if dep.contains("/") {
-            let tokens: Vec<&str> = dep.split("/").collect();
-            if tokens.len() >= 2 {
-                group_id = tokens[0].to_string();
-                artifact = tokens[1].to_string();
+        let spec = match self::parse_dep_spec(&dep) {
+            Ok(spec) => spec,
+            Err(e) => {
+                println!("{}", e);
+                failed.push(dep);
+                continue;
             }
-        }
+        };
 
-        if artifact.contains("@") {
-            if let Some((a, v)) = artifact.split_once("@") {
-                let a = a.to_string();
-                let v = v.to_string();
-                artifact = a;
-                version = v;
-            }
-        }
+        let version = spec.version.clone().unwrap_or_default();
 
-        let results = self::search_deps(&group_id, &artifact, &version).await;
+        let group_id = match &spec.group_id {
+            Some(group_id) => group_id.clone(),
+            None => match self::find_group_id(&grind, &spec.artifact, options.yes).await {
+                Ok(group_id) => group_id,
+                Err(e) => {
+                    println!("{}", e);
+                    failed.push(dep);
+                    continue;
+                }
+            },
+        };
+
+        let results = self::search_deps(&group_id, &spec.artifact, &version).await;
 
         match results {
-            Some(matched_dep) => candidates.push(matched_dep),
+            Some(matched_dep) => candidates.push(Dependency {
+                scope: Some(options.scope.clone()),
+                classifier: options.classifier.clone(),
+                exclusions: if options.exclusions.is_empty() {
+                    None
+                } else {
+                    Some(options.exclusions.clone())
+                },
+                ..matched_dep
+            }),
             None => {
-                println!(
-                    "❌ WARNING: no match found for {}/{} v{}",
-                    group_id, artifact, version
-                );
+                if version.is_empty() {
+                    println!(
+                        "❌ WARNING: no match found for {}/{}",
+                        group_id, spec.artifact
+                    );
+                } else {
+                    println!(
+                        "❌ WARNING: no match found for {}/{} v{}",
+                        group_id, spec.artifact, version
+                    );
+                }
+                self::suggest_candidates(&grind, &group_id, &spec.artifact).await;
+                failed.push(dep);
             }
         }
     }
     // now that we have a list of resolved candidates lets sync the grind.yml and install
     if !candidates.is_empty() {
-        self::update_grind(grind, candidates).await;
+        self::update_grind(grind, candidates).await?;
+    }
+
+    if !failed.is_empty() {
+        return Err(GrindError::Resolution(format!(
+            "❌ unable to add: {}",
+            failed.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+fn validate_options(options: &AddOptions) -> Result<(), String> {
+    let is_valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '*'))
+    };
+
+    if let Some(classifier) = &options.classifier
+        && !is_valid(classifier)
+    {
+        return Err(format!("⚠️ invalid classifier '{}'", classifier));
+    }
+
+    for exclusion in &options.exclusions {
+        let parts: Vec<&str> = exclusion.split(':').collect();
+        if parts.len() > 2 || !parts.iter().all(|p| is_valid(p)) {
+            return Err(format!(
+                "⚠️ invalid exclusion '{}', expected <groupId>[:<artifactId>] e.g commons-logging:commons-logging",
+                exclusion
+            ));
+        }
+    }
+    Ok(())
+}
+
+async fn find_group_id(grind: &Grind, artifact: &str, yes: bool) -> Result<String, String> {
+    let index = search::get_index(Some(grind), None);
+
+    let results = index
+        .search(artifact, 10)
+        .await
+        .map_err(|e| format!("❌ Unable to search for '{}': {}", artifact, e))?;
+
+    if results.is_empty() {
+        return Err(format!(
+            "❌ no artifacts found for '{}', try <groupId>/<artifactId>",
+            artifact
+        ));
+    }
+
+    let chosen = self::choose_candidate(artifact, &results, yes)?;
+    Ok(chosen.groupId.clone())
+}
+
+fn choose_candidate<'a>(
+    artifact: &str,
+    results: &'a [SearchResult],
+    yes: bool,
+) -> Result<&'a SearchResult, String> {
+    // results are already ranked, exact artifactId matches first then by popularity/recency
+    if results.len() == 1 || yes {
+        let chosen = &results[0];
+        println!(
+            "🔎 using {}/{} for '{}'",
+            chosen.groupId, chosen.artifactId, artifact
+        );
+        return Ok(chosen);
+    }
+
+    if !io::stdin().is_terminal() {
+        println!("🔎 found {} candidates for '{}':", results.len(), artifact);
+        search::print_results(results);
+        return Err(format!(
+            "❌ '{}' is ambiguous, use <groupId>/<artifactId> or pass --yes to pick the top match",
+            artifact
+        ));
+    }
+
+    println!(
+        "🔎 found {} candidates for '{}':\n",
+        results.len(),
+        artifact
+    );
+    for (i, result) in results.iter().enumerate() {
+        println!(
+            " {:>2}) {}/{} v{}",
+            i + 1,
+            result.groupId,
+            result.artifactId,
+            result.latestVersion
+        );
+    }
+
+    loop {
+        print!(
+            "\nselect a dependency [1-{}] (default 1, q to skip): ",
+            results.len()
+        );
+        io::stdout().flush().map_err(|e| e.to_string())?;
+
+        let mut answer = String::new();
+        io::stdin()
+            .read_line(&mut answer)
+            .map_err(|e| e.to_string())?;
+        let answer = answer.trim();
+
+        if answer.is_empty() {
+            return Ok(&results[0]);
+        }
+        if answer.eq_ignore_ascii_case("q") {
+            return Err(format!("⏭️ skipping '{}'", artifact));
+        }
+        match answer.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= results.len() => return Ok(&results[n - 1]),
+            _ => println!("⚠️ '{}' is not a valid choice", answer),
+        }
     }
 }
 
-pub async fn execute_remove(mut grind: Grind, deps: Vec<String>) {
+async fn suggest_candidates(grind: &Grind, group_id: &str, artifact: &str) {
+    let index = search::get_index(Some(grind), None);
+
+    // a wrong groupId is the most common mistake, so search by the artifact name alone
+    let term = if artifact.is_empty() {
+        group_id
+    } else {
+        artifact
+    };
+    if term.is_empty() {
+        return;
+    }
+
+    if let Ok(results) = index.search(term, 5).await
+        && !results.is_empty()
+    {
+        println!("💡 did you mean one of these?");
+        search::print_results(&results);
+    }
+}
+
+pub async fn execute_remove(mut grind: Grind, deps: Vec<String>) -> Result<(), GrindError> {
     let mut candidates = Vec::new();
+    let mut failed = Vec::new();
 
     for dep in deps {
-        let mut group_id = String::new();
-        let mut artifact = String::new();
-
-        if dep.contains("/") {
-            let tokens: Vec<&str> = dep.split("/").collect();
-            if tokens.len() >= 2 {
-                group_id = tokens[0].to_string();
-                artifact = tokens[1].to_string();
+        let spec = match self::parse_dep_spec(&dep) {
+            Ok(spec) if spec.version.is_some() => {
+                println!("⚠️ '{}' ignoring the version, removing by name only", dep);
+                spec
             }
-        }
+            Ok(spec) => spec,
+            Err(e) => {
+                println!("{}", e);
+                failed.push(dep);
+                continue;
+            }
+        };
+
+        let artifact = spec.artifact;
+
+        // without a groupId we can only remove it if the artifactId is unique in the grind.yml
+        let group_id = match spec.group_id {
+            Some(group_id) => group_id,
+            None => {
+                let matches = grind
+                    .project
+                    .dependencies
+                    .iter()
+                    .filter(|x| x.artifactId == artifact)
+                    .collect::<Vec<_>>();
+                match matches.as_slice() {
+                    [only] => only.groupId.clone(),
+                    [] => {
+                        println!("❌ WARNING: no match found for {}", artifact);
+                        failed.push(dep);
+                        continue;
+                    }
+                    _ => {
+                        println!(
+                            "❌ '{}' matches more than one dependency, use <groupId>/<artifactId>",
+                            artifact
+                        );
+                        failed.push(dep);
+                        continue;
+                    }
+                }
+            }
+        };
 
         if let Some(index) = grind
             .project
@@ -73,34 +362,144 @@ pub async fn execute_remove(mut grind: Grind, deps: Vec<String>) {
             grind.project.dependencies.remove(index);
         } else {
             println!("❌ WARNING: no match found for {}/{}", group_id, artifact);
+            failed.push(dep);
         }
     }
 
-    let resolved = install::resolve_all_deps(candidates).await;
+    let result = if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(GrindError::Resolution(format!(
+            "❌ unable to remove: {}",
+            failed.join(", ")
+        )))
+    };
 
-    for dep in resolved {
-        if let Err(e) = self::delete_jar(&dep) {
-            println!("❌ Failed to download {:?}: {:?}", dep, e);
+    if candidates.is_empty() {
+        return result;
+    }
+
+    /* ---------------------------------------------------------------------------------------------
+    other dependencies may still need some of the removed dependency's transitive deps (e.g
+    slf4j-api), so re-resolve what's left and only delete the jars that are no longer reachable.
+
+    the grind.lock is written here directly, as `install` merges in the previously locked deps.
+    --------------------------------------------------------------------------------------------- */
+    let (old_locked, input_processors, locked_processors) = lock::get_lock_file()
+        .map(|l| (l.lockedDeps, l.inputProcessors, l.lockedProcessors))
+        .unwrap_or_default();
+
+    let constraints = grind.project.constraints.clone().unwrap_or_default();
+    let (resolved, forced, divergent) =
+        install::resolve_graph(&grind.project.dependencies, &constraints).await;
+
+    self::print_diff(&self::get_diff(&old_locked, &resolved));
+
+    // the grind.yml goes first, a failure there must leave the jars and the grind.lock untouched
+    if !manifest::sync_dependencies(&grind, &[], &candidates) {
+        return Err(GrindError::Other(
+            "❌ grind.yml was not updated".to_string(),
+        ));
+    }
+
+    let keep: HashSet<String> = resolved.iter().map(install::get_jar_path).collect();
+
+    for dep in &old_locked {
+        if !keep.contains(&install::get_jar_path(dep))
+            && let Err(e) = self::delete_jar(dep)
+            && e.kind() != io::ErrorKind::NotFound
+        {
+            println!("❌ Failed to delete {:?}: {:?}", dep, e);
         }
     }
 
-    if let Ok(updated) = serde_yaml::to_string(&grind) {
-        if fs::write("grind.yml", updated).is_ok() {
-            println!("🔃 grind.yml synced..");
-            // run install again
-            install::execute_install(grind).await;
+    lock::lock_file(&Lock {
+        inputDeps: grind.project.dependencies.clone(),
+        inputConstraints: constraints,
+        lockedDeps: resolved,
+        forcedDeps: forced,
+        inputProcessors: input_processors,
+        lockedProcessors: locked_processors,
+        divergentDeps: Some(divergent),
+    });
+    // run install again
+    install::execute_install(grind).await?;
+
+    result
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct DepDiff {
+    removed: Vec<Dependency>,
+    changed: Vec<(Dependency, Dependency)>,
+    added: Vec<Dependency>,
+}
+
+fn get_diff(old: &[Dependency], new: &[Dependency]) -> DepDiff {
+    let key = |dep: &Dependency| {
+        (
+            dep.groupId.clone(),
+            dep.artifactId.clone(),
+            dep.classifier.clone(),
+        )
+    };
+
+    let old_map: BTreeMap<_, _> = old.iter().map(|d| (key(d), d)).collect();
+    let new_map: BTreeMap<_, _> = new.iter().map(|d| (key(d), d)).collect();
+
+    let mut diff = DepDiff::default();
+
+    for (k, before) in &old_map {
+        match new_map.get(k) {
+            None => diff.removed.push((*before).clone()),
+            Some(after) if before.version != after.version || before.scope != after.scope => {
+                diff.changed.push(((*before).clone(), (*after).clone()))
+            }
+            Some(_) => {}
         }
-    } else {
-        println!("⚠️ Unable to sync grind.yml!")
     }
+    for (k, after) in &new_map {
+        if !old_map.contains_key(k) {
+            diff.added.push((*after).clone());
+        }
+    }
+    diff
 }
 
-fn delete_jar(dep: &Dependency) -> Result<(), std::io::Error> {
-    let artifact = &dep.artifactId;
-    let version = &dep.version;
+fn print_diff(diff: &DepDiff) {
+    if diff.removed.is_empty() && diff.changed.is_empty() && diff.added.is_empty() {
+        println!("ℹ️ no changes to the resolved dependencies");
+        return;
+    }
+
+    println!("\n📋 dependency changes:");
+    for dep in &diff.removed {
+        println!("  - {}:{}:{}", dep.groupId, dep.artifactId, dep.version);
+    }
+    for (before, after) in &diff.changed {
+        println!(
+            "  ~ {}:{} {} ({}) -> {} ({})",
+            before.groupId,
+            before.artifactId,
+            before.version,
+            before.scope.as_deref().unwrap_or("compile"),
+            after.version,
+            after.scope.as_deref().unwrap_or("compile")
+        );
+    }
+    for dep in &diff.added {
+        println!("  + {}:{}:{}", dep.groupId, dep.artifactId, dep.version);
+    }
+    println!(
+        "\n  {} removed, {} changed, {} added\n",
+        diff.removed.len(),
+        diff.changed.len(),
+        diff.added.len()
+    );
+}
 
-    let jar_name = format!("{}_{}_{}.jar", dep.groupId, artifact, version);
-    let local_path = format!("libs/{}", jar_name);
+fn delete_jar(dep: &Dependency) -> Result<(), std::io::Error> {
+    let local_path = install::get_jar_path(dep);
 
     std::fs::remove_file(&local_path)?;
 
@@ -120,6 +519,7 @@ async fn search_deps(group_id: &str, artifact: &str, version: &str) -> Option<De
                     artifactId: artifact.to_string(),
                     version: matched,
                     scope: Some("compile".to_string()),
+                    ..Default::default()
                 });
             } else {
                 return None;
@@ -134,26 +534,93 @@ async fn search_deps(group_id: &str, artifact: &str, version: &str) -> Option<De
                 artifactId: artifact.to_string(),
                 version: v,
                 scope: Some("compile".to_string()),
+                ..Default::default()
             });
         }
     }
     None
 }
 
-async fn update_grind(mut grind: Grind, candidates: Vec<Dependency>) {
-    for dep in candidates {
-        if !grind.project.dependencies.contains(&dep) {
-            grind.project.dependencies.push(dep.clone());
+async fn update_grind(mut grind: Grind, candidates: Vec<Dependency>) -> Result<(), GrindError> {
+    for dep in &candidates {
+        // re-adding an existing dependency updates it e.g to change the scope or version
+        match grind.project.dependencies.iter_mut().find(|x| {
+            x.groupId == dep.groupId
+                && x.artifactId == dep.artifactId
+                && x.classifier == dep.classifier
+        }) {
+            Some(existing) => *existing = dep.clone(),
+            None => grind.project.dependencies.push(dep.clone()),
         }
     }
 
-    if let Ok(updated) = serde_yaml::to_string(&grind) {
-        if fs::write("grind.yml", updated).is_ok() {
-            println!("🔃 grind.yml synced..");
+    if !manifest::sync_dependencies(&grind, &candidates, &[]) {
+        return Err(GrindError::Other(
+            "❌ grind.yml was not updated".to_string(),
+        ));
+    }
+    install::execute_install(grind).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            install::execute_install(grind).await;
+    #[test]
+    fn test_parse_dep_spec() {
+        assert_eq!(
+            parse_dep_spec("org.postgresql/postgresql@42.7.7"),
+            Ok(DepSpec {
+                group_id: Some("org.postgresql".to_string()),
+                artifact: "postgresql".to_string(),
+                version: Some("42.7.7".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_dep_spec("jackson-databind"),
+            Ok(DepSpec {
+                group_id: None,
+                artifact: "jackson-databind".to_string(),
+                version: None,
+            })
+        );
+
+        for invalid in [
+            "",
+            "a/b/c",
+            "org.postgresql/",
+            "/postgresql",
+            "postgresql@",
+            "a b",
+        ] {
+            assert!(
+                parse_dep_spec(invalid).is_err(),
+                "'{}' should be invalid",
+                invalid
+            );
         }
-    } else {
-        println!("⚠️ Unable to sync grind.yml!")
+    }
+
+    #[test]
+    fn test_get_diff() {
+        let dep = |artifact: &str, version: &str| Dependency {
+            groupId: "org.example".to_string(),
+            artifactId: artifact.to_string(),
+            version: version.to_string(),
+            scope: Some("compile".to_string()),
+            ..Default::default()
+        };
+
+        let old = vec![
+            dep("slf4j-api", "2.0.9"),
+            dep("logback", "1.5.0"),
+            dep("a", "1"),
+        ];
+        let new = vec![dep("slf4j-api", "2.0.9"), dep("a", "2")];
+
+        let diff = get_diff(&old, &new);
+        assert_eq!(diff.removed, vec![dep("logback", "1.5.0")]);
+        assert_eq!(diff.changed, vec![(dep("a", "1"), dep("a", "2"))]);
+        assert!(diff.added.is_empty());
     }
 }
@@ -0,0 +1,337 @@
+use crate::Grind;
+use crate::config::Dependency;
+use std::fs;
+
+/* -------------------------------------------------------------------------------------------------
+Format preserving edits of the `grind.yml`, rather than round-tripping the whole file through serde
+(which loses comments, quoting, blank lines and the order of the tasks), only the lines of the
+dependency entries that actually changed are touched e.g:
+
+    dependencies:
+      # our database driver
+      - groupId: "org.postgresql"
+        artifactId: "postgresql"
+        version: "42.7.7"
+
+NOTE: this is line based, not a full YAML parser, it only understands a block style `dependencies`
+list (or an empty `[]`) under `project:`. Anything else falls back to a full rewrite.
+------------------------------------------------------------------------------------------------- */
+
+const GRIND_FILE: &str = "grind.yml";
+
+pub fn sync_dependencies(grind: &Grind, upserts: &[Dependency], removals: &[Dependency]) -> bool {
+    let edited = fs::read_to_string(GRIND_FILE)
+        .map_err(|e| e.to_string())
+        .and_then(|source| self::edit_dependencies(&source, upserts, removals))
+        .and_then(|edited| self::check_edit(&edited, grind).map(|_| edited));
+
+    let updated = match edited {
+        Ok(edited) => edited,
+        Err(e) => {
+            println!(
+                "⚠️ unable to preserve the grind.yml formatting ({}), rewriting it...",
+                e
+            );
+            match serde_yaml::to_string(grind) {
+                Ok(updated) => updated,
+                Err(e) => {
+                    println!("⚠️ Unable to sync grind.yml! {}", e);
+                    return false;
+                }
+            }
+        }
+    };
+
+    if fs::write(GRIND_FILE, updated).is_ok() {
+        println!("🔃 grind.yml synced..");
+        true
+    } else {
+        println!("⚠️ Unable to sync grind.yml!");
+        false
+    }
+}
+
+fn check_edit(edited: &str, grind: &Grind) -> Result<(), String> {
+    // the edit must parse back into exactly the dependencies we meant to write
+    let parsed: Grind = serde_yaml::from_str(edited).map_err(|e| e.to_string())?;
+    if parsed.project.dependencies != grind.project.dependencies {
+        return Err("edited dependencies don't match".to_string());
+    }
+    Ok(())
+}
+
+pub fn edit_dependencies(
+    source: &str,
+    upserts: &[Dependency],
+    removals: &[Dependency],
+) -> Result<String, String> {
+    let mut lines: Vec<String> = source.split_inclusive('\n').map(String::from).collect();
+    if let Some(last) = lines.last_mut()
+        && !last.ends_with('\n')
+    {
+        last.push('\n');
+    }
+
+    for dep in removals {
+        let list = self::find_dependencies(&lines)?;
+        if let Some(item) = list.items.iter().find(|item| self::is_same(&item.dep, dep)) {
+            lines.drain(item.start..item.end);
+
+            if list.items.len() == 1 {
+                lines[list.key_line] = format!("{}dependencies: []\n", " ".repeat(list.key_indent));
+            }
+        }
+    }
+
+    for dep in upserts {
+        let list = self::find_dependencies(&lines)?;
+        match list.items.iter().find(|item| self::is_same(&item.dep, dep)) {
+            Some(item) => {
+                let rendered = self::render(dep, list.item_indent);
+                lines.splice(item.start..item.end, rendered);
+            }
+            None => {
+                if list.items.is_empty() {
+                    lines[list.key_line] =
+                        format!("{}dependencies:\n", " ".repeat(list.key_indent));
+                }
+                let rendered = self::render(dep, list.item_indent);
+                lines.splice(list.insert_at..list.insert_at, rendered);
+            }
+        }
+    }
+
+    let mut edited = lines.concat();
+    if !source.ends_with('\n') {
+        edited.pop();
+    }
+    Ok(edited)
+}
+
+fn is_same(a: &Dependency, b: &Dependency) -> bool {
+    a.groupId == b.groupId && a.artifactId == b.artifactId && a.classifier == b.classifier
+}
+
+fn render(dep: &Dependency, indent: usize) -> Vec<String> {
+    // JSON strings are valid double quoted YAML scalars, which matches the `grind new` style
+    let quote = |value: &str| serde_json::to_string(value).unwrap_or_default();
+    let pad = " ".repeat(indent);
+
+    let mut lines = vec![
+        format!("{}- groupId: {}\n", pad, quote(&dep.groupId)),
+        format!("{}  artifactId: {}\n", pad, quote(&dep.artifactId)),
+        format!("{}  version: {}\n", pad, quote(&dep.version)),
+    ];
+    if let Some(scope) = &dep.scope {
+        lines.push(format!("{}  scope: {}\n", pad, quote(scope)));
+    }
+    if let Some(classifier) = &dep.classifier {
+        lines.push(format!("{}  classifier: {}\n", pad, quote(classifier)));
+    }
+    if let Some(exclusions) = &dep.exclusions {
+        let quoted: Vec<String> = exclusions.iter().map(|e| quote(e)).collect();
+        lines.push(format!("{}  exclusions: [{}]\n", pad, quoted.join(", ")));
+    }
+    lines
+}
+
+struct DependencyList {
+    key_line: usize,
+    key_indent: usize,
+    item_indent: usize,
+    items: Vec<Item>,
+    // where a new entry goes, straight after the last entry's content
+    insert_at: usize,
+}
+
+struct Item {
+    start: usize,
+    end: usize,
+    dep: Dependency,
+}
+
+fn get_indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#')
+}
+
+fn strip_comment(value: &str) -> &str {
+    match value.find(" #") {
+        Some(index) => value[..index].trim(),
+        None => value.trim(),
+    }
+}
+
+fn find_dependencies(lines: &[String]) -> Result<DependencyList, String> {
+    let project = lines
+        .iter()
+        .position(|l| get_indent(l) == 0 && l.trim_end().starts_with("project:"))
+        .ok_or("no `project:` section found")?;
+
+    // the project block runs until the next top level key
+    let block_end = (project + 1..lines.len())
+        .find(|&i| is_content(&lines[i]) && get_indent(&lines[i]) == 0)
+        .unwrap_or(lines.len());
+
+    let child_indent = (project + 1..block_end)
+        .find(|&i| is_content(&lines[i]))
+        .map(|i| get_indent(&lines[i]))
+        .ok_or("the `project:` section is empty")?;
+
+    let key_line = (project + 1..block_end)
+        .find(|&i| {
+            get_indent(&lines[i]) == child_indent
+                && lines[i].trim_start().starts_with("dependencies:")
+        })
+        .ok_or("no `dependencies:` found under `project:`")?;
+
+    let value = strip_comment(&lines[key_line].trim_start()["dependencies:".len()..]);
+    if !value.is_empty() && value != "[]" {
+        return Err("only a block style `dependencies` list can be edited".to_string());
+    }
+
+    // YAML allows the `-` to sit at the same indent as the key
+    let first = (key_line + 1..block_end).find(|&i| is_content(&lines[i]));
+    let item_indent = match first {
+        Some(i)
+            if value.is_empty()
+                && get_indent(&lines[i]) >= child_indent
+                && lines[i].trim_start().starts_with('-') =>
+        {
+            get_indent(&lines[i])
+        }
+        _ => child_indent + 2,
+    };
+
+    let mut items: Vec<Item> = Vec::new();
+    let mut insert_at = key_line + 1;
+
+    if value.is_empty() {
+        let mut current: Option<usize> = None;
+        let mut last_content = key_line;
+
+        for i in key_line + 1..block_end {
+            let line = &lines[i];
+            if !is_content(line) {
+                continue;
+            }
+            let indent = get_indent(line);
+            let is_item = indent == item_indent && line.trim_start().starts_with('-');
+
+            if !is_item && indent <= item_indent {
+                break;
+            }
+            if is_item {
+                if let Some(start) = current {
+                    items.push(self::parse_item(
+                        lines,
+                        start,
+                        last_content + 1,
+                        item_indent,
+                    )?);
+                }
+                current = Some(i);
+            }
+            last_content = i;
+        }
+        if let Some(start) = current {
+            items.push(self::parse_item(
+                lines,
+                start,
+                last_content + 1,
+                item_indent,
+            )?);
+        }
+        insert_at = last_content + 1;
+    }
+
+    Ok(DependencyList {
+        key_line,
+        key_indent: child_indent,
+        item_indent,
+        items,
+        insert_at,
+    })
+}
+
+fn parse_item(lines: &[String], start: usize, end: usize, indent: usize) -> Result<Item, String> {
+    let chunk: String = lines[start..end]
+        .iter()
+        .map(|l| l.get(indent..).unwrap_or(l.trim_start()))
+        .collect();
+
+    let mut parsed: Vec<Dependency> =
+        serde_yaml::from_str(&chunk).map_err(|e| format!("line {}: {}", start + 1, e))?;
+
+    match parsed.pop() {
+        Some(dep) if parsed.is_empty() => Ok(Item { start, end, dep }),
+        _ => Err(format!("line {}: unexpected dependency entry", start + 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"# our payments service
+project:
+  groupId: "com.example"
+  artifactId: "PaymentsApi"
+  version: "1.0.0"
+  name: "PaymentsApi"
+  description: "Update me!"
+
+  dependencies:
+    # the database driver
+    - groupId: 'org.postgresql'
+      artifactId: postgresql
+      version: "42.7.6"
+
+    - { groupId: "junit", artifactId: "junit", version: "4.13.2", scope: "test" }
+
+  tasks:
+    zzz: "echo last"   # keep me last
+    clean: "rm -rf target/"
+"#;
+
+    #[test]
+    fn test_edit_dependencies() {
+        let dep = |group: &str, artifact: &str, version: &str| Dependency {
+            groupId: group.to_string(),
+            artifactId: artifact.to_string(),
+            version: version.to_string(),
+            scope: Some("compile".to_string()),
+            ..Default::default()
+        };
+
+        let upserts = [
+            dep("org.postgresql", "postgresql", "42.7.7"),
+            dep("io.javalin", "javalin", "6.3.0"),
+        ];
+        let removals = [dep("junit", "junit", "4.13.2")];
+
+        let edited = edit_dependencies(MANIFEST, &upserts, &removals).unwrap();
+
+        // comments, blank lines and the task order are all left alone
+        assert!(edited.starts_with("# our payments service\n"));
+        assert!(edited.contains("    # the database driver\n    - groupId: \"org.postgresql\""));
+        assert!(edited.contains("    zzz: \"echo last\"   # keep me last\n    clean:"));
+        assert!(!edited.contains("junit"));
+
+        let parsed: Grind = serde_yaml::from_str(&edited).unwrap();
+        assert_eq!(parsed.project.dependencies, upserts.to_vec());
+
+        // removing the last one leaves an empty, but still valid, list
+        let emptied = edit_dependencies(&edited, &[], &upserts).unwrap();
+        let parsed: Grind = serde_yaml::from_str(&emptied).unwrap();
+        assert!(parsed.project.dependencies.is_empty());
+
+        let readded = edit_dependencies(&emptied, &upserts[..1], &[]).unwrap();
+        let parsed: Grind = serde_yaml::from_str(&readded).unwrap();
+        assert_eq!(parsed.project.dependencies, upserts[..1].to_vec());
+    }
+}
@@ -6,6 +6,7 @@ use serde_path_to_error as path;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::ops::{Deref, DerefMut};
+use std::path::Path;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PomId {
@@ -77,6 +78,56 @@ pub async fn get_effective_dependencies(
     }
 }
 
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PomLicense {
+    pub name: Option<String>,
+    pub url: Option<String>,
+}
+
+pub async fn get_licenses(pom_id: &PomId) -> Vec<PomLicense> {
+    self::get_licenses_from(pom_id, Path::new(install::POM_CACHE)).await
+}
+
+async fn get_licenses_from(pom_id: &PomId, cache: &Path) -> Vec<PomLicense> {
+    let mut visited = HashSet::new();
+    let mut current = Some(pom_id.clone());
+
+    // licenses are inherited from the parent POM(s) when the child doesn't declare any
+    while let Some(id) = current {
+        if !visited.insert(id.clone()) {
+            break;
+        }
+
+        let pom_xml = install::get_pom_from(
+            config::Dependency {
+                groupId: id.group_id.clone(),
+                artifactId: id.artifact_id.clone(),
+                version: id.version.clone(),
+                scope: Some("compile".to_string()),
+                ..Default::default()
+            },
+            cache,
+        )
+        .await;
+
+        let pom = match parse_pom_from_str(&pom_xml) {
+            Ok(pom) => pom,
+            Err(_) => break,
+        };
+
+        if !pom.licenses.license.is_empty() {
+            return pom.licenses.license;
+        }
+
+        current = pom.parent.map(|parent| PomId {
+            group_id: parent.group_id,
+            artifact_id: parent.artifact_id,
+            version: parent.version,
+        });
+    }
+    Vec::new()
+}
+
 #[derive(Debug, Clone, Default)]
 struct ResolutionContext {
     dependency_management: HashMap<String, Dependency>,
@@ -93,6 +144,7 @@ async fn resolve_context_recursive(
             artifactId: pom_id.artifact_id.clone(),
             version: pom_id.version.clone(),
             scope: Some("compile".to_string()),
+            ..Default::default()
         })
         .await;
 
@@ -109,6 +161,7 @@ async fn resolve_context_recursive(
         artifactId: pom_id.artifact_id.clone(),
         version: pom_id.version.clone(),
         scope: Some("compile".to_string()),
+        ..Default::default()
     })
     .await;
 
@@ -232,6 +285,14 @@ struct Pom {
     dependency_management: DependencyManagement,
     #[serde(default)]
     dependencies: Dependencies,
+    #[serde(default)]
+    licenses: Licenses,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+struct Licenses {
+    #[serde(default)]
+    license: Vec<PomLicense>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -312,3 +373,88 @@ struct Dependency {
     #[serde(default)]
     optional: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::FAKE_POM;
+
+    #[test]
+    fn test_parse_licenses() {
+        let pom = parse_pom_from_str(FAKE_POM).expect("Failed to parse POM");
+
+        assert_eq!(pom.licenses.license.len(), 1);
+        assert_eq!(
+            pom.licenses.license[0].name.as_deref(),
+            Some("Eclipse Public License 1.0")
+        );
+        assert_eq!(
+            pom.licenses.license[0].url.as_deref(),
+            Some("http://www.eclipse.org/legal/epl-v10.html")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_licenses_inherited_from_parent() {
+        // the POMs are read from the cache first, so seeding a temp one keeps this offline
+        let cache = std::env::temp_dir().join(format!("grind-licenses-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&cache);
+        std::fs::create_dir_all(&cache).unwrap();
+
+        let group = "com.example".to_string();
+        let child = cache.join("com.example_child_1.0.pom");
+        let parent = cache.join("com.example_parent_3.pom");
+        std::fs::write(
+            &child,
+            format!(
+                r#"<project>
+  <parent>
+    <groupId>{}</groupId>
+    <artifactId>parent</artifactId>
+    <version>3</version>
+  </parent>
+  <artifactId>child</artifactId>
+  <version>1.0</version>
+</project>"#,
+                group
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            &parent,
+            format!(
+                r#"<project>
+  <groupId>{}</groupId>
+  <artifactId>parent</artifactId>
+  <version>3</version>
+  <licenses>
+    <license>
+      <name>Apache License, Version 2.0</name>
+      <url>https://www.apache.org/licenses/LICENSE-2.0.txt</url>
+    </license>
+  </licenses>
+</project>"#,
+                group
+            ),
+        )
+        .unwrap();
+
+        let licenses = get_licenses_from(
+            &PomId {
+                group_id: group,
+                artifact_id: "child".to_string(),
+                version: "1.0".to_string(),
+            },
+            &cache,
+        )
+        .await;
+
+        let _ = std::fs::remove_dir_all(&cache);
+
+        assert_eq!(licenses.len(), 1);
+        assert_eq!(
+            licenses[0].name.as_deref(),
+            Some("Apache License, Version 2.0")
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use crate::Grind;
+use crate::RunArgs;
+use regex::Captures;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/* -------------------------------------------------------------------------------------------------
+Copies `src/main/resources` into target/, followed by the profile's own `resources:` folders (if
+any) layered on top, i.e a file in `src/main/resources-prod` replaces the base file at the same path.
+
+Only the files matching one of the `filterResources:` globs are filtered, everything else is copied
+byte for byte e.g
+
+    filterResources: ["application.properties", "*.yml"]
+    properties:
+      api.url: "https://api.example.com"
+
+the placeholders that get substituted:
+
+    ${project.groupId}, ${project.artifactId}, ${project.version}, ${project.name}
+    ${env.NAME}         the profile's envs
+    ${name}             the custom `properties:`
+
+any other placeholder (e.g Spring's own `${server.port:8080}`) is left as is. In the globs `*`
+matches within a folder, `**` across folders and `?` a single character.
+
+target/ isn't wiped between builds, so whatever the previous build copied (listed in the STATE_FILE)
+but this one didn't e.g a deleted resource, or another profile's overlay, is removed.
+------------------------------------------------------------------------------------------------- */
+
+const RESOURCE_DIR: &str = "src/main/resources";
+const TARGET_DIR: &str = "target";
+const STATE_FILE: &str = "cache/resources.txt";
+
+pub fn copy_resources(grind: &Grind, args: &RunArgs) -> io::Result<()> {
+    let filters = grind
+        .project
+        .filterResources
+        .iter()
+        .flatten()
+        .map(|glob| self::glob_to_regex(glob))
+        .collect::<Vec<_>>();
+    let values = self::get_values(grind, &args.envs);
+    let dirs = std::iter::once(RESOURCE_DIR)
+        .chain(args.resources.iter().map(String::as_str))
+        .map(Path::new)
+        .collect::<Vec<_>>();
+
+    self::copy_dirs(
+        &dirs,
+        Path::new(TARGET_DIR),
+        Path::new(STATE_FILE),
+        &filters,
+        &values,
+    )
+}
+
+fn copy_dirs(
+    dirs: &[&Path],
+    target: &Path,
+    state: &Path,
+    filters: &[Regex],
+    values: &HashMap<String, String>,
+) -> io::Result<()> {
+    let mut copied = BTreeSet::new();
+
+    for dir in dirs {
+        // a missing profile folder is already reported by the grind.yml checks
+        if !dir.exists() {
+            continue;
+        }
+
+        for entry in WalkDir::new(dir).into_iter().flatten() {
+            let Ok(relative) = entry.path().strip_prefix(dir) else {
+                continue;
+            };
+            let destination = target.join(relative);
+
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&destination)?;
+                continue;
+            }
+
+            let path = relative.to_string_lossy().replace('\\', "/");
+            self::copy_file(entry.path(), &destination, &path, filters, values)?;
+            copied.insert(path);
+        }
+    }
+
+    self::prune(&copied, target, state)
+}
+
+fn copy_file(
+    from: &Path,
+    to: &Path,
+    path: &str,
+    filters: &[Regex],
+    values: &HashMap<String, String>,
+) -> io::Result<()> {
+    if !filters.iter().any(|filter| filter.is_match(path)) {
+        return fs::copy(from, to).map(|_| ());
+    }
+
+    // a glob can easily catch a binary file, those are copied as is
+    match fs::read_to_string(from) {
+        Ok(content) => fs::write(to, self::substitute(&content, values)),
+        Err(_) => {
+            println!("⚠️ {} is not a text file, copied without filtering", path);
+            fs::copy(from, to).map(|_| ())
+        }
+    }
+}
+
+fn prune(copied: &BTreeSet<String>, target: &Path, state: &Path) -> io::Result<()> {
+    let previous = fs::read_to_string(state).unwrap_or_default();
+    for path in previous.lines().filter(|path| !copied.contains(*path)) {
+        let _ = fs::remove_file(target.join(path));
+    }
+
+    if let Some(dir) = state.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(
+        state,
+        copied
+            .iter()
+            .map(|path| format!("{}\n", path))
+            .collect::<String>(),
+    )
+}
+
+fn get_values(grind: &Grind, envs: &[(String, String)]) -> HashMap<String, String> {
+    let project = &grind.project;
+    let mut values: HashMap<String, String> = project.properties.clone().unwrap_or_default();
+
+    values.extend([
+        ("project.groupId".to_string(), project.groupId.clone()),
+        ("project.artifactId".to_string(), project.artifactId.clone()),
+        ("project.version".to_string(), project.version.clone()),
+        ("project.name".to_string(), project.name.clone()),
+    ]);
+    values.extend(
+        envs.iter()
+            .map(|(key, value)| (format!("env.{}", key), value.clone())),
+    );
+    values
+}
+
+fn substitute(content: &str, values: &HashMap<String, String>) -> String {
+    let placeholder = Regex::new(r"\$\{([^${}]+)\}").unwrap();
+
+    placeholder
+        .replace_all(content, |caps: &Captures| match values.get(&caps[1]) {
+            Some(value) => value.clone(),
+            None => caps[0].to_string(),
+        })
+        .to_string()
+}
+
+fn glob_to_regex(glob: &str) -> Regex {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // a leading folder wildcard also matches no folder at all e.g application.yml
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    pattern.push_str("(.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+
+    // every character is either escaped or one of the above, so this can't fail
+    Regex::new(&pattern).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filtering() {
+        let glob = glob_to_regex("**/*.properties");
+        assert!(glob.is_match("application.properties"));
+        assert!(glob.is_match("config/db.properties"));
+        assert!(!glob.is_match("logo.png"));
+
+        let glob = glob_to_regex("*.yml");
+        assert!(glob.is_match("application.yml"));
+        assert!(!glob.is_match("config/application.yml"));
+
+        let values = HashMap::from([
+            ("project.version".to_string(), "1.2.0".to_string()),
+            ("env.DB_URL".to_string(), "jdbc:h2:mem:dev".to_string()),
+        ]);
+        assert_eq!(
+            substitute(
+                "app.version=${project.version}\ndb.url=${env.DB_URL}\nport=${server.port:8080}\n",
+                &values
+            ),
+            "app.version=1.2.0\ndb.url=jdbc:h2:mem:dev\nport=${server.port:8080}\n"
+        );
+    }
+
+    #[test]
+    fn test_overlay_and_prune() {
+        let root = std::env::temp_dir().join(format!("grind-resources-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        let base = root.join("resources");
+        let prod = root.join("resources-prod");
+        let dev = root.join("resources-dev");
+        let target = root.join("target");
+        let state = root.join("cache/resources.txt");
+
+        for (dir, file, content) in [
+            (&base, "application.properties", "env=base"),
+            (&base, "logback.xml", "<configuration/>"),
+            (&prod, "application.properties", "env=prod"),
+            (&prod, "prod-only.txt", "prod"),
+            (&dev, "config/dev-only.txt", "dev"),
+        ] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let values = HashMap::new();
+
+        // the prod overlay replaces the base file at the same path
+        copy_dirs(&[&base, &prod], &target, &state, &[], &values).unwrap();
+        assert_eq!(
+            fs::read_to_string(target.join("application.properties")).unwrap(),
+            "env=prod"
+        );
+        assert!(target.join("logback.xml").exists());
+        assert!(target.join("prod-only.txt").exists());
+
+        // switching to dev drops the prod overlay, and its version of the base file
+        copy_dirs(&[&base, &dev], &target, &state, &[], &values).unwrap();
+        assert_eq!(
+            fs::read_to_string(target.join("application.properties")).unwrap(),
+            "env=base"
+        );
+        assert!(!target.join("prod-only.txt").exists());
+        assert!(target.join("config/dev-only.txt").exists());
+
+        // and back to the base resources only
+        copy_dirs(&[&base], &target, &state, &[], &values).unwrap();
+        assert!(!target.join("config/dev-only.txt").exists());
+        assert!(target.join("logback.xml").exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::Grind;
+use crate::config::Dependency;
+use crate::config::Rules;
+use crate::install;
+use crate::java;
+use crate::util;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use zip::ZipArchive;
+
+/* -------------------------------------------------------------------------------------------------
+"Enforcer" style rules, checked during install and build e.g:
+
+    rules:
+      bannedDependencies: ["commons-logging:*", "log4j:log4j:1.*"]
+      dependencyConvergence: true
+      maxBytecodeVersion: 17
+      requireJavaVersion: 21
+
+NOTE: the "newest wins" collision strategy silently hides conflicting versions, so convergence must
+be checked against the resolved graph *before* any collisions are fixed.
+
+NOTE: everything but maxBytecodeVersion only needs the coordinates, so `enforce` runs before anything
+is downloaded, `enforce_jars` once the (still staged) jars are there but before they reach libs/.
+------------------------------------------------------------------------------------------------- */
+
+pub fn enforce(grind: &Grind, deps: &[Dependency], divergent: Option<&[Dependency]>) -> bool {
+    let rules = match &grind.project.rules {
+        Some(rules) => rules,
+        None => return true,
+    };
+
+    println!("📏 checking project rules...");
+
+    let mut violations = Vec::new();
+
+    if let Some(required) = rules.requireJavaVersion {
+        violations.extend(self::check_java_version(required));
+    }
+
+    violations.extend(self::check_banned(rules, deps));
+
+    if rules.dependencyConvergence {
+        match divergent {
+            Some(divergent) => violations.extend(self::check_convergence(divergent)),
+            None => violations.push(
+                "[dependencyConvergence] the grind.lock has no convergence data, run `grind install` to refresh it"
+                    .to_string(),
+            ),
+        }
+    }
+
+    self::report(&violations)
+}
+
+pub fn enforce_jars(grind: &Grind, deps: &[Dependency]) -> bool {
+    let Some(max) = grind
+        .project
+        .rules
+        .as_ref()
+        .and_then(|rules| rules.maxBytecodeVersion)
+    else {
+        return true;
+    };
+
+    println!("📏 checking the bytecode version of the jars...");
+    self::report(&self::check_bytecode(max, deps))
+}
+
+fn report(violations: &[String]) -> bool {
+    if violations.is_empty() {
+        println!("✅ All project rules passed.");
+        return true;
+    }
+
+    println!("❌ {} rule violation(s):", violations.len());
+    for violation in violations {
+        println!("   - {}", violation);
+    }
+    false
+}
+
+fn check_java_version(required: u32) -> Vec<String> {
+    match java::get_java_version(true) {
+        Ok(version) => match self::get_java_major(&version) {
+            Some(major) if major < required => vec![format!(
+                "[requireJavaVersion] Java {} or newer is required, found v{}",
+                required, version
+            )],
+            Some(_) => Vec::new(),
+            None => vec![format!(
+                "[requireJavaVersion] unable to parse the Java version v{}",
+                version
+            )],
+        },
+        Err(e) => vec![format!("[requireJavaVersion] {}", e)],
+    }
+}
+
+pub fn get_java_major(version: &str) -> Option<u32> {
+    // e.g "21.0.2", "17", or the legacy "1.8.0_292" format
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse::<u32>().ok()?;
+
+    if major == 1 {
+        return parts.next()?.parse::<u32>().ok();
+    }
+    Some(major)
+}
+
+fn check_banned(rules: &Rules, deps: &[Dependency]) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    for dep in deps {
+        if let Some(pattern) = rules
+            .bannedDependencies
+            .iter()
+            .find(|pattern| self::is_banned(pattern, dep))
+        {
+            violations.push(format!(
+                "[bannedDependencies] {}:{}:{} is banned by '{}'",
+                dep.groupId, dep.artifactId, dep.version, pattern
+            ));
+        }
+    }
+    violations.sort();
+    violations
+}
+
+pub fn is_banned(pattern: &str, dep: &Dependency) -> bool {
+    // groupId[:artifactId[:version]], each part can be a `*` or end with a `*` wildcard
+    let parts: Vec<&str> = pattern.split(':').collect();
+    let values = [&dep.groupId, &dep.artifactId, &dep.version];
+
+    if parts.is_empty() || parts.len() > values.len() {
+        return false;
+    }
+
+    parts
+        .iter()
+        .zip(values.iter())
+        .all(|(part, value)| match part.strip_suffix('*') {
+            Some(prefix) => value.starts_with(prefix),
+            None => *part == value.as_str(),
+        })
+}
+
+pub fn get_divergent(unconverged: &HashSet<Dependency>) -> Vec<Dependency> {
+    // only the conflicting deps are worth keeping in the grind.lock, not the whole graph
+    let mut versions: HashMap<(&str, &str), HashSet<&str>> = HashMap::new();
+    for dep in unconverged {
+        versions
+            .entry((&dep.groupId, &dep.artifactId))
+            .or_default()
+            .insert(&dep.version);
+    }
+
+    let mut divergent: Vec<Dependency> = unconverged
+        .iter()
+        .filter(|dep| versions[&(dep.groupId.as_str(), dep.artifactId.as_str())].len() > 1)
+        .map(|dep| Dependency {
+            groupId: dep.groupId.clone(),
+            artifactId: dep.artifactId.clone(),
+            version: dep.version.clone(),
+            ..Default::default()
+        })
+        .collect();
+    divergent.sort_by(|a, b| {
+        (&a.groupId, &a.artifactId)
+            .cmp(&(&b.groupId, &b.artifactId))
+            .then_with(|| util::compare_maven_versions(&a.version, &b.version))
+    });
+    divergent.dedup();
+    divergent
+}
+
+fn check_convergence(divergent: &[Dependency]) -> Vec<String> {
+    let mut versions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for dep in divergent {
+        let entry = versions
+            .entry(format!("{}:{}", dep.groupId, dep.artifactId))
+            .or_default();
+        if !entry.contains(&dep.version) {
+            entry.push(dep.version.clone());
+        }
+    }
+
+    versions
+        .into_iter()
+        .filter(|(_, found)| found.len() > 1)
+        .map(|(key, mut found)| {
+            found.sort_by(|a, b| util::compare_maven_versions(a, b));
+            format!(
+                "[dependencyConvergence] {} has conflicting versions: {} (use `constraints` to pick one)",
+                key,
+                found.join(", ")
+            )
+        })
+        .collect()
+}
+
+fn check_bytecode(max_java: u32, deps: &[Dependency]) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    for dep in deps {
+        let jar_path = install::get_downloaded_path(dep);
+        match self::get_max_bytecode(&jar_path) {
+            Ok(Some(java)) if java > max_java => violations.push(format!(
+                "[maxBytecodeVersion] {}:{}:{} requires Java {}, the maximum allowed is Java {}",
+                dep.groupId, dep.artifactId, dep.version, java, max_java
+            )),
+            Ok(_) => {}
+            Err(e) => println!("⚠️ unable to inspect {}: {}", jar_path, e),
+        }
+    }
+    violations
+}
+
+fn get_max_bytecode(jar_path: &str) -> Result<Option<u32>, String> {
+    /* ---------------------------------------------------------------------------------------------
+    every class file starts with: u4 magic (0xCAFEBABE), u2 minor, u2 major, where the major version
+    maps onto the Java release as `major - 44` e.g 61 => Java 17.
+
+    multi-release jars keep newer classes under META-INF/versions/, those are only loaded by newer
+    JVMs so they are skipped, as is module-info.class.
+    --------------------------------------------------------------------------------------------- */
+    let file = File::open(jar_path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    let mut max = None;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        let name = entry.name().to_string();
+
+        if !name.ends_with(".class")
+            || name.starts_with("META-INF/")
+            || name.ends_with("module-info.class")
+        {
+            continue;
+        }
+
+        let mut header = [0u8; 8];
+        if entry.read_exact(&mut header).is_err() || header[0..4] != [0xCA, 0xFE, 0xBA, 0xBE] {
+            continue;
+        }
+
+        let major = u16::from_be_bytes([header[6], header[7]]) as u32;
+        let java = major.saturating_sub(44);
+        max = Some(max.map_or(java, |m: u32| m.max(java)));
+    }
+
+    Ok(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_banned_and_convergence() {
+        let dep = |group: &str, artifact: &str, version: &str| Dependency {
+            groupId: group.to_string(),
+            artifactId: artifact.to_string(),
+            version: version.to_string(),
+            scope: None,
+            ..Default::default()
+        };
+
+        let logging = dep("commons-logging", "commons-logging", "1.2");
+        assert!(is_banned("commons-logging:*", &logging));
+        assert!(is_banned("commons-logging", &logging));
+        assert!(!is_banned("commons-logging:commons-logging:1.1*", &logging));
+        assert!(is_banned(
+            "log4j:log4j:1.*",
+            &dep("log4j", "log4j", "1.2.17")
+        ));
+        assert!(!is_banned("log4j:log4j:1.*", &dep("log4j", "log4j", "2.0")));
+
+        let mut resolved = HashSet::new();
+        resolved.insert(dep("org.slf4j", "slf4j-api", "2.0.9"));
+        resolved.insert(dep("org.slf4j", "slf4j-api", "1.7.36"));
+        resolved.insert(dep("junit", "junit", "4.13.2"));
+
+        let divergent = get_divergent(&resolved);
+        assert_eq!(divergent.len(), 2);
+        assert_eq!(divergent[0].version, "1.7.36");
+
+        let violations = check_convergence(&divergent);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].contains("org.slf4j:slf4j-api"));
+        assert!(violations[0].contains("1.7.36, 2.0.9"));
+
+        assert_eq!(get_java_major("21.0.2"), Some(21));
+        assert_eq!(get_java_major("1.8.0_292"), Some(8));
+    }
+}
@@ -1,24 +1,32 @@
 use crate::Grind;
 use crate::RunArgs;
 use crate::build;
-use crate::util::shell_stream;
+use crate::error::GrindError;
+use crate::java;
+use crate::util;
 
 use crate::BuildTarget;
 
-pub fn execute_run(grind: Grind, args: &RunArgs) {
-    build::execute_build(&grind, BuildTarget::BuildOnly, args.flags.to_string());
+pub fn execute_run(grind: Grind, args: &RunArgs) -> Result<(), GrindError> {
+    build::execute_build(&grind, BuildTarget::BuildOnly, args)?;
+    let main_class = build::get_main_class(&grind, args.bin.as_deref())?;
+
     println!("==> 🚀 running project [{}]...", grind.project.artifactId);
 
-    let mut cmd = format!(
-        "java -cp \"target:libs/*\" {}.{} {}",
-        grind.project.groupId,
-        grind.project.artifactId,
-        args.args.join(" ")
-    );
+    let status = util::exec(
+        java::command("java")
+            .args(["-cp", "target:libs/*"])
+            .arg(&main_class)
+            .args(&args.args)
+            .envs(args.envs.iter().map(|(k, v)| (k, v))),
+    )?;
 
-    if !args.envs.is_empty() {
-        cmd = format!("{} {}", args.envs, cmd);
+    // the application's exit code becomes ours, so scripts can rely on it
+    if !status.success() {
+        return Err(GrindError::Process(
+            format!("Process exited with: {}", status),
+            util::get_exit_code(&status),
+        ));
     }
-    // println!("DEBUG: using cmd -> '{}'", cmd);
-    let _ = shell_stream(&cmd);
+    Ok(())
 }
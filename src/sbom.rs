@@ -0,0 +1,557 @@
+use crate::Grind;
+use crate::config::Dependency;
+use crate::error::GrindError;
+use crate::install;
+use crate::license;
+use crate::lock;
+use crate::pom::PomLicense;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use clap::ValueEnum;
+use serde_json::Value;
+use serde_json::json;
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/* -------------------------------------------------------------------------------------------------
+Generates a Software Bill of Materials from the grind.lock, everything is read from the local cache
+(POMs under `cache/`, jars under `libs/`) so this can run without any network access, as long as a
+`grind install` has been run at some point.
+------------------------------------------------------------------------------------------------- */
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SbomFormat {
+    CyclonedxJson,
+    SpdxJson,
+}
+
+struct Component {
+    dep: Dependency,
+    purl: String,
+    sha256: Option<String>,
+    md5: Option<String>,
+    licenses: Vec<PomLicense>,
+    depends_on: Vec<String>,
+}
+
+pub async fn execute_sbom(
+    grind: Grind,
+    format: SbomFormat,
+    output: Option<PathBuf>,
+) -> Result<(), GrindError> {
+    let locked = match lock::get_lock_file() {
+        Ok(locked) => locked,
+        Err(e) => {
+            return Err(GrindError::Resolution(format!(
+                "❌ Unable to read grind.lock, try `grind install` first: {}",
+                e
+            )));
+        }
+    };
+
+    install::set_offline(true);
+
+    println!("⚙️ collecting licenses and relationships from the local cache...");
+    let licenses = license::get_licenses(&locked.lockedDeps).await;
+    let graph = install::get_dependency_graph(&locked.lockedDeps).await;
+
+    let components = locked
+        .lockedDeps
+        .iter()
+        .map(|dep| {
+            let (sha256, md5) = match fs::read(install::get_jar_path(dep)) {
+                Ok(bytes) => (
+                    Some(format!("{:x}", Sha256::digest(&bytes))),
+                    Some(format!("{:x}", md5::compute(&bytes))),
+                ),
+                Err(_) => {
+                    println!(
+                        "⚠️ missing jar for {}:{}:{}, no hashes will be recorded",
+                        dep.groupId, dep.artifactId, dep.version
+                    );
+                    (None, None)
+                }
+            };
+
+            Component {
+                dep: dep.clone(),
+                purl: self::get_purl(dep),
+                sha256,
+                md5,
+                licenses: licenses.get(dep).cloned().unwrap_or_default(),
+                depends_on: graph
+                    .get(dep)
+                    .map(|edges| edges.iter().map(self::get_purl).collect())
+                    .unwrap_or_default(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // the direct dependencies are the ones in the grind.yml, mapped onto their locked versions
+    let direct = grind
+        .project
+        .dependencies
+        .iter()
+        .filter_map(|dep| {
+            components.iter().find(|c| {
+                c.dep.groupId == dep.groupId
+                    && c.dep.artifactId == dep.artifactId
+                    && c.dep.classifier == dep.classifier
+            })
+        })
+        .map(|c| c.purl.clone())
+        .collect::<Vec<_>>();
+
+    let (document, default_output) = match format {
+        SbomFormat::CyclonedxJson => (
+            self::to_cyclonedx(&grind, &components, &direct),
+            "sbom.cdx.json",
+        ),
+        SbomFormat::SpdxJson => (
+            self::to_spdx(&grind, &components, &direct),
+            "sbom.spdx.json",
+        ),
+    };
+
+    let output = output.unwrap_or(PathBuf::from(default_output));
+
+    let json = serde_json::to_string_pretty(&document)
+        .map_err(|e| format!("❌ Unable to serialize SBOM: {}", e))?;
+    fs::write(&output, json)
+        .map_err(|e| format!("❌ Unable to write {}: {}", output.display(), e))?;
+
+    println!(
+        "✅ SBOM with {} component(s) written to {}",
+        components.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn get_purl(dep: &Dependency) -> String {
+    // a classifier is a purl qualifier, otherwise e.g the natives jars would share one bom-ref
+    let qualifiers = dep
+        .classifier
+        .as_ref()
+        .map(|c| format!("?classifier={}", c))
+        .unwrap_or_default();
+    format!(
+        "pkg:maven/{}/{}@{}{}",
+        dep.groupId, dep.artifactId, dep.version, qualifiers
+    )
+}
+
+fn get_serial(grind: &Grind, timestamp: &str) -> String {
+    let hash = format!(
+        "{:x}",
+        md5::compute(format!(
+            "{}:{}:{}:{}",
+            grind.project.groupId, grind.project.artifactId, grind.project.version, timestamp
+        ))
+    );
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hash[0..8],
+        &hash[8..12],
+        &hash[12..16],
+        &hash[16..20],
+        &hash[20..32]
+    )
+}
+
+fn to_cyclonedx(grind: &Grind, components: &[Component], direct: &[String]) -> Value {
+    let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    let root = format!(
+        "pkg:maven/{}/{}@{}",
+        grind.project.groupId, grind.project.artifactId, grind.project.version
+    );
+
+    let mut dependencies = vec![json!({ "ref": root, "dependsOn": direct })];
+
+    let components = components
+        .iter()
+        .map(|c| {
+            dependencies.push(json!({ "ref": c.purl, "dependsOn": c.depends_on }));
+
+            let mut hashes = Vec::new();
+            if let Some(sha256) = &c.sha256 {
+                hashes.push(json!({ "alg": "SHA-256", "content": sha256 }));
+            }
+            if let Some(md5) = &c.md5 {
+                hashes.push(json!({ "alg": "MD5", "content": md5 }));
+            }
+
+            let licenses = c
+                .licenses
+                .iter()
+                .map(|l| match license::get_spdx_id(l) {
+                    Some(id) => json!({ "license": { "id": id } }),
+                    None => {
+                        let mut license = json!({
+                            "name": l.name.clone().unwrap_or("Unknown".to_string()),
+                        });
+                        if let Some(url) = &l.url {
+                            license["url"] = json!(url);
+                        }
+                        json!({ "license": license })
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let scope = c.dep.scope.as_deref().unwrap_or("compile");
+
+            json!({
+                "type": "library",
+                "bom-ref": c.purl,
+                "group": c.dep.groupId,
+                "name": c.dep.artifactId,
+                "version": c.dep.version,
+                "scope": match scope {
+                    "test" | "provided" => "optional",
+                    _ => "required",
+                },
+                "hashes": hashes,
+                "licenses": licenses,
+                "purl": c.purl,
+                "properties": [{ "name": "maven:scope", "value": scope }],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", self::get_serial(grind, &timestamp)),
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp,
+            "tools": [{ "vendor": "grind", "name": "grind", "version": env!("CARGO_PKG_VERSION") }],
+            "component": {
+                "type": "application",
+                "bom-ref": root,
+                "group": grind.project.groupId,
+                "name": grind.project.artifactId,
+                "version": grind.project.version,
+                "description": grind.project.description,
+                "purl": root,
+            }
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+fn to_spdx(grind: &Grind, components: &[Component], direct: &[String]) -> Value {
+    let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    let root_id = "SPDXRef-Package-root".to_string();
+
+    let ids: HashMap<&str, String> = components
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let id = format!(
+                "SPDXRef-Package-{}-{}",
+                c.dep
+                    .artifactId
+                    .replace(|ch: char| !ch.is_ascii_alphanumeric(), "-"),
+                i
+            );
+            (c.purl.as_str(), id)
+        })
+        .collect();
+
+    let mut packages = vec![json!({
+        "name": grind.project.artifactId,
+        "SPDXID": root_id,
+        "versionInfo": grind.project.version,
+        "supplier": "NOASSERTION",
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": "NOASSERTION",
+        "externalRefs": [{
+            "referenceCategory": "PACKAGE-MANAGER",
+            "referenceType": "purl",
+            "referenceLocator": format!(
+                "pkg:maven/{}/{}@{}",
+                grind.project.groupId, grind.project.artifactId, grind.project.version
+            ),
+        }],
+    })];
+
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": root_id,
+    })];
+
+    for purl in direct {
+        if let Some(id) = ids.get(purl.as_str()) {
+            relationships.push(json!({
+                "spdxElementId": root_id,
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": id,
+            }));
+        }
+    }
+
+    let mut extracted: HashMap<String, String> = HashMap::new();
+
+    for c in components {
+        let id = &ids[c.purl.as_str()];
+
+        let mut checksums = Vec::new();
+        if let Some(sha256) = &c.sha256 {
+            checksums.push(json!({ "algorithm": "SHA256", "checksumValue": sha256 }));
+        }
+        if let Some(md5) = &c.md5 {
+            checksums.push(json!({ "algorithm": "MD5", "checksumValue": md5 }));
+        }
+
+        // SPDX expects a license *expression*, anything we can't map goes in as a LicenseRef
+        let declared = c
+            .licenses
+            .iter()
+            .map(|l| match license::get_spdx_id(l) {
+                Some(id) => id.to_string(),
+                None => {
+                    let name = license::get_display_name(l);
+                    let license_ref = format!(
+                        "LicenseRef-{}",
+                        name.replace(|ch: char| !ch.is_ascii_alphanumeric() && ch != '.', "-")
+                    );
+                    extracted.insert(license_ref.clone(), name);
+                    license_ref
+                }
+            })
+            .collect::<Vec<_>>();
+
+        packages.push(json!({
+            "name": c.dep.artifactId,
+            "SPDXID": id,
+            "versionInfo": c.dep.version,
+            "supplier": format!("Organization: {}", c.dep.groupId),
+            "downloadLocation": install::build_jar_url(&c.dep),
+            "filesAnalyzed": false,
+            "checksums": checksums,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": if declared.is_empty() {
+                "NOASSERTION".to_string()
+            } else {
+                declared.join(" OR ")
+            },
+            "comment": format!("maven scope: {}", c.dep.scope.as_deref().unwrap_or("compile")),
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": c.purl,
+            }],
+        }));
+
+        for edge in &c.depends_on {
+            if let Some(related) = ids.get(edge.as_str()) {
+                relationships.push(json!({
+                    "spdxElementId": id,
+                    "relationshipType": "DEPENDS_ON",
+                    "relatedSpdxElement": related,
+                }));
+            }
+        }
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": format!("{}-{}", grind.project.artifactId, grind.project.version),
+        "documentNamespace": format!(
+            "https://spdx.org/spdxdocs/{}-{}-{}",
+            grind.project.artifactId,
+            grind.project.version,
+            self::get_serial(grind, &timestamp)
+        ),
+        "creationInfo": {
+            "created": timestamp,
+            "creators": [format!("Tool: grind-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+        "hasExtractedLicensingInfos": extracted
+            .into_iter()
+            .map(|(id, name)| json!({ "licenseId": id, "name": name, "extractedText": name }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cyclonedx_and_spdx() {
+        let raw = r#"
+project:
+  groupId: "com.example"
+  artifactId: "demo"
+  version: "1.0.0"
+  name: "demo"
+  description: "demo"
+  dependencies: []
+  tasks: {}
+"#;
+        let grind: Grind = serde_yaml::from_str(raw).unwrap();
+
+        let dep = |group: &str, artifact: &str, version: &str| Dependency {
+            groupId: group.to_string(),
+            artifactId: artifact.to_string(),
+            version: version.to_string(),
+            scope: Some("compile".to_string()),
+            ..Default::default()
+        };
+        let client = dep("org.apache.httpcomponents", "httpclient", "4.5.14");
+        let core = dep("org.apache.httpcomponents", "httpcore", "4.4.16");
+
+        let components = vec![
+            Component {
+                purl: get_purl(&client),
+                dep: client,
+                sha256: Some("abc".to_string()),
+                md5: None,
+                licenses: vec![PomLicense {
+                    name: Some("Apache License, Version 2.0".to_string()),
+                    url: Some("https://www.apache.org/licenses/LICENSE-2.0.txt".to_string()),
+                }],
+                depends_on: vec![get_purl(&core)],
+            },
+            Component {
+                purl: get_purl(&core),
+                dep: core,
+                sha256: None,
+                md5: None,
+                licenses: vec![PomLicense {
+                    name: Some("Custom License".to_string()),
+                    url: None,
+                }],
+                depends_on: Vec::new(),
+            },
+        ];
+        let direct = vec!["pkg:maven/org.apache.httpcomponents/httpclient@4.5.14".to_string()];
+
+        let bom = to_cyclonedx(&grind, &components, &direct);
+        // the Apache license maps onto its SPDX id, so it's written as an id
+        assert_eq!(
+            bom["metadata"]["component"]["purl"],
+            "pkg:maven/com.example/demo@1.0.0"
+        );
+        assert_eq!(bom["components"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            bom["components"][0]["purl"],
+            "pkg:maven/org.apache.httpcomponents/httpclient@4.5.14"
+        );
+        assert_eq!(bom["components"][0]["hashes"][0]["content"], "abc");
+        assert_eq!(
+            bom["components"][0]["licenses"][0]["license"]["id"],
+            "Apache-2.0"
+        );
+        // no url in the POM, so no url key at all
+        assert_eq!(
+            bom["components"][1]["licenses"][0]["license"],
+            json!({ "name": "Custom License" })
+        );
+        assert_eq!(
+            bom["dependencies"],
+            json!([
+                { "ref": "pkg:maven/com.example/demo@1.0.0", "dependsOn": direct },
+                {
+                    "ref": "pkg:maven/org.apache.httpcomponents/httpclient@4.5.14",
+                    "dependsOn": ["pkg:maven/org.apache.httpcomponents/httpcore@4.4.16"]
+                },
+                { "ref": "pkg:maven/org.apache.httpcomponents/httpcore@4.4.16", "dependsOn": [] },
+            ])
+        );
+
+        let spdx = to_spdx(&grind, &components, &direct);
+        let packages = spdx["packages"].as_array().unwrap();
+        assert_eq!(packages.len(), 3);
+        assert_eq!(
+            packages[2]["externalRefs"][0]["referenceLocator"],
+            "pkg:maven/org.apache.httpcomponents/httpcore@4.4.16"
+        );
+
+        // DESCRIBES, root => httpclient and httpclient => httpcore
+        let edges = spdx["relationships"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                format!(
+                    "{} {} {}",
+                    r["spdxElementId"].as_str().unwrap(),
+                    r["relationshipType"].as_str().unwrap(),
+                    r["relatedSpdxElement"].as_str().unwrap()
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            vec![
+                "SPDXRef-DOCUMENT DESCRIBES SPDXRef-Package-root",
+                "SPDXRef-Package-root DEPENDS_ON SPDXRef-Package-httpclient-0",
+                "SPDXRef-Package-httpclient-0 DEPENDS_ON SPDXRef-Package-httpcore-1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classifier_purls() {
+        let raw = r#"
+project:
+  groupId: "com.example"
+  artifactId: "demo"
+  version: "1.0.0"
+  name: "demo"
+  description: "demo"
+  dependencies: []
+  tasks: {}
+"#;
+        let grind: Grind = serde_yaml::from_str(raw).unwrap();
+
+        let lwjgl = |classifier: Option<&str>| Dependency {
+            groupId: "org.lwjgl".to_string(),
+            artifactId: "lwjgl".to_string(),
+            version: "3.3.4".to_string(),
+            classifier: classifier.map(str::to_string),
+            ..Default::default()
+        };
+        assert_eq!(get_purl(&lwjgl(None)), "pkg:maven/org.lwjgl/lwjgl@3.3.4");
+        assert_eq!(
+            get_purl(&lwjgl(Some("natives-linux"))),
+            "pkg:maven/org.lwjgl/lwjgl@3.3.4?classifier=natives-linux"
+        );
+
+        let components = [lwjgl(None), lwjgl(Some("natives-linux"))]
+            .into_iter()
+            .map(|dep| Component {
+                purl: get_purl(&dep),
+                dep,
+                sha256: None,
+                md5: None,
+                licenses: Vec::new(),
+                depends_on: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+
+        // both end up as their own component/package
+        let bom = to_cyclonedx(&grind, &components, &[]);
+        assert_ne!(
+            bom["components"][0]["bom-ref"],
+            bom["components"][1]["bom-ref"]
+        );
+        let spdx = to_spdx(&grind, &components, &[]);
+        assert_eq!(spdx["packages"].as_array().unwrap().len(), 3);
+        assert_ne!(spdx["packages"][1]["SPDXID"], spdx["packages"][2]["SPDXID"]);
+    }
+}
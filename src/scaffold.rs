@@ -1,4 +1,5 @@
 use crate::LOGO;
+use crate::schema;
 
 pub fn create(namespace: &str, artifact_id: &str) {
     println!("{}", LOGO);
@@ -10,6 +11,7 @@ pub fn create(namespace: &str, artifact_id: &str) {
     /src/
         - /java/main/<namespace>/<artifactId.java>
     /grind.yml
+    /grind.schema.json
 
     --------------------------------------------------------------------------------------------- */
     self::create_project_dir(artifact_id);
@@ -20,6 +22,7 @@ pub fn create(namespace: &str, artifact_id: &str) {
     self::create_src_and_namespace(namespace, artifact_id);
     self::create_java_file(namespace, artifact_id);
     self::create_grind_file(namespace, artifact_id);
+    self::create_schema_file(artifact_id);
 
     println!();
     println!("🎉🎉 created project {}/ successfully!", artifact_id);
@@ -32,6 +35,9 @@ fn create_project_dir(artifact_id: &str) {
 
 fn create_gitignore_file(artifact_id: &str) {
     let gitignore: &str = r#"libs/*
+libs-processors/*
+libs-sources/*
+libs-test/*
 target/*
 build/*
 cache/*
@@ -74,8 +80,8 @@ $ grind build
 
 fn create_vs_code_settings(artifact_id: &str) {
     let settings: &str = r#"{
-  "java.project.referencedLibraries": ["libs/*"],
-  "java.project.sourcePaths": ["src/main/java"]
+  "java.project.referencedLibraries": ["libs/*", "libs-test/*"],
+  "java.project.sourcePaths": ["src/main/java", "target/generated-sources"]
 }
 "#;
 
@@ -125,12 +131,14 @@ public class <ARTIFACT_ID> {
 }
 
 fn create_grind_file(namespace: &str, artifact_id: &str) {
-    let grind: &str = r#"project:
+    let grind: &str = r#"# yaml-language-server: $schema=./grind.schema.json
+project:
   groupId: "<NAMESPACE>"
   artifactId: "<ARTIFACT_ID>"
   version: "1.0.0"
   name: "My App"
   description: "Update me!"
+  mainClass: "<NAMESPACE>.<ARTIFACT_ID>"
 
   dependencies:      
     - groupId: "junit"
@@ -148,3 +156,12 @@ fn create_grind_file(namespace: &str, artifact_id: &str) {
     std::fs::write(format!("{}/grind.yml", artifact_id), grind).unwrap();
     println!("==> created grind.yml file");
 }
+
+fn create_schema_file(artifact_id: &str) {
+    std::fs::write(
+        format!("{}/{}", artifact_id, schema::SCHEMA_FILE),
+        format!("{}\n", schema::get_schema()),
+    )
+    .unwrap();
+    println!("==> created {} file", schema::SCHEMA_FILE);
+}
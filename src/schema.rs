@@ -0,0 +1,36 @@
+use crate::config::Grind;
+use crate::error::GrindError;
+use std::fs;
+use std::path::PathBuf;
+
+/* -------------------------------------------------------------------------------------------------
+The JSON Schema of the `grind.yml` is generated straight from the `config` structs, so any new field
+shows up in the editor (autocomplete, validation, hover docs) without having to document it by hand.
+
+Editors using the YAML language server (e.g VS Code's YAML extension) pick it up from the modeline
+that `grind new` puts at the top of the grind.yml:
+
+    # yaml-language-server: $schema=./grind.schema.json
+------------------------------------------------------------------------------------------------- */
+
+pub const SCHEMA_FILE: &str = "grind.schema.json";
+
+pub fn get_schema() -> String {
+    let mut schema = schemars::schema_for!(Grind);
+    schema.insert("title".to_string(), "grind.yml".into());
+    serde_json::to_string_pretty(&schema).unwrap_or_default()
+}
+
+pub fn execute_schema(output: Option<PathBuf>) -> Result<(), GrindError> {
+    let schema = self::get_schema();
+
+    match output {
+        Some(path) => {
+            fs::write(&path, format!("{}\n", schema))
+                .map_err(|e| format!("❌ Unable to write {}: {}", path.display(), e))?;
+            println!("✅ JSON schema written to {}", path.display());
+        }
+        None => println!("{}", schema),
+    }
+    Ok(())
+}
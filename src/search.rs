@@ -0,0 +1,249 @@
+use crate::Grind;
+use crate::error::GrindError;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
+
+/* -------------------------------------------------------------------------------------------------
+Searching for artifacts goes through the `ArtifactIndex` trait, so the source can be swapped out e.g
+Maven Central's search API, or a plain local index file:
+
+    searchIndex: ./maven-index.json
+
+where the file is a JSON list of:
+
+    [{ "groupId": "io.javalin", "artifactId": "javalin", "latestVersion": "6.3.0", "versionCount": 120 }]
+------------------------------------------------------------------------------------------------- */
+
+// how many hits are fetched from Maven Central before ranking, however small the `--limit`
+const SEARCH_WINDOW: usize = 100;
+
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct SearchResult {
+    pub groupId: String,
+    pub artifactId: String,
+    pub latestVersion: String,
+    #[serde(default)]
+    pub versionCount: u64,
+    #[serde(default)]
+    pub timestamp: i64,
+}
+
+pub trait ArtifactIndex {
+    async fn search(&self, term: &str, limit: usize) -> Result<Vec<SearchResult>, String>;
+}
+
+pub struct MavenCentralIndex;
+
+pub struct FileIndex {
+    pub path: PathBuf,
+}
+
+pub enum Index {
+    Central(MavenCentralIndex),
+    File(FileIndex),
+}
+
+impl ArtifactIndex for Index {
+    async fn search(&self, term: &str, limit: usize) -> Result<Vec<SearchResult>, String> {
+        match self {
+            Index::Central(index) => index.search(term, limit).await,
+            Index::File(index) => index.search(term, limit).await,
+        }
+    }
+}
+
+pub fn get_index(grind: Option<&Grind>, path: Option<PathBuf>) -> Index {
+    let configured = grind
+        .and_then(|g| g.project.searchIndex.clone())
+        .map(PathBuf::from);
+
+    match path.or(configured) {
+        Some(path) => Index::File(FileIndex { path }),
+        None => Index::Central(MavenCentralIndex),
+    }
+}
+
+pub async fn execute_search(
+    grind: Option<Grind>,
+    term: String,
+    limit: usize,
+    path: Option<PathBuf>,
+) -> Result<(), GrindError> {
+    let index = self::get_index(grind.as_ref(), path);
+
+    match index.search(&term, limit).await {
+        Ok(results) if results.is_empty() => println!("❌ no artifacts found for '{}'", term),
+        Ok(results) => {
+            println!("\nFound {} artifact(s):\n", results.len());
+            self::print_results(&results);
+            println!();
+        }
+        Err(e) => {
+            return Err(GrindError::Other(format!(
+                "❌ Unable to search for '{}': {}",
+                term, e
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn print_results(results: &[SearchResult]) {
+    let width = results
+        .iter()
+        .map(|r| r.groupId.len() + r.artifactId.len() + 1)
+        .max()
+        .unwrap_or(0);
+
+    for result in results {
+        println!(
+            " - {:<width$}  v{}",
+            format!("{}/{}", result.groupId, result.artifactId),
+            result.latestVersion,
+            width = width
+        );
+    }
+}
+
+fn rank(term: &str, a: &SearchResult, b: &SearchResult) -> Ordering {
+    // exact artifactId matches first, then the most "popular" (most released versions)
+    let term = term.to_lowercase();
+    let exact_a = a.artifactId.to_lowercase() == term;
+    let exact_b = b.artifactId.to_lowercase() == term;
+
+    exact_b
+        .cmp(&exact_a)
+        .then(b.versionCount.cmp(&a.versionCount))
+        .then(b.timestamp.cmp(&a.timestamp))
+}
+
+impl ArtifactIndex for MavenCentralIndex {
+    async fn search(&self, term: &str, limit: usize) -> Result<Vec<SearchResult>, String> {
+        #[derive(Deserialize)]
+        struct Response {
+            response: Docs,
+        }
+
+        #[derive(Deserialize)]
+        struct Docs {
+            docs: Vec<Doc>,
+        }
+
+        #[allow(non_snake_case)]
+        #[derive(Deserialize)]
+        struct Doc {
+            g: String,
+            a: String,
+            latestVersion: Option<String>,
+            #[serde(default)]
+            versionCount: u64,
+            #[serde(default)]
+            timestamp: i64,
+        }
+
+        // `group/artifact` (or `group:artifact`) narrows the search down to a coordinate
+        let query = match term.split_once(['/', ':']) {
+            Some((g, a)) => format!("g:{} AND a:{}", g, a),
+            None => term.to_string(),
+        };
+
+        let url = format!(
+            "https://search.maven.org/solrsearch/select?q={}&rows={}&wt=json",
+            urlencoding::encode(&query),
+            limit.max(SEARCH_WINDOW)
+        );
+
+        println!("🌎 Searching Maven Central for '{}'", term);
+
+        let json = reqwest::get(&url)
+            .await
+            .map_err(|e| e.to_string())?
+            .text()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let response: Response = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+
+        let mut results = response
+            .response
+            .docs
+            .into_iter()
+            .filter_map(|doc| {
+                Some(SearchResult {
+                    groupId: doc.g,
+                    artifactId: doc.a,
+                    latestVersion: doc.latestVersion?,
+                    versionCount: doc.versionCount,
+                    timestamp: doc.timestamp,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // ranked on our side, Solr's own relevance order would bury e.g an exact artifactId match
+        results.sort_by(|a, b| self::rank(term, a, b));
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+impl ArtifactIndex for FileIndex {
+    async fn search(&self, term: &str, limit: usize) -> Result<Vec<SearchResult>, String> {
+        let raw = fs::read_to_string(&self.path)
+            .map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        let entries: Vec<SearchResult> =
+            serde_json::from_str(&raw).map_err(|e| format!("{}: {}", self.path.display(), e))?;
+
+        let needle = term.to_lowercase().replace(':', "/");
+
+        let mut results = entries
+            .into_iter()
+            .filter(|entry| {
+                format!("{}/{}", entry.groupId, entry.artifactId)
+                    .to_lowercase()
+                    .contains(&needle)
+            })
+            .collect::<Vec<_>>();
+
+        results.sort_by(|a, b| self::rank(term, a, b));
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_index_search() {
+        let path = std::env::temp_dir().join(format!("grind-index-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"[
+                { "groupId": "com.fasterxml.jackson.core", "artifactId": "jackson-core", "latestVersion": "2.18.0", "versionCount": 150 },
+                { "groupId": "com.fasterxml.jackson.core", "artifactId": "jackson-databind", "latestVersion": "2.18.0", "versionCount": 180 },
+                { "groupId": "org.example", "artifactId": "jackson-databind-extras", "latestVersion": "0.1.0", "versionCount": 300 },
+                { "groupId": "org.postgresql", "artifactId": "postgresql", "latestVersion": "42.7.7", "versionCount": 90 }
+            ]"#,
+        )
+        .unwrap();
+
+        let index = FileIndex { path: path.clone() };
+
+        let results = index.search("jackson-databind", 10).await.unwrap();
+        assert_eq!(results.len(), 2);
+        // exact artifactId match wins over the more popular partial match
+        assert_eq!(results[0].artifactId, "jackson-databind");
+
+        let results = index.search("org.postgresql/postgresql", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].latestVersion, "42.7.7");
+
+        assert_eq!(index.search("jackson", 1).await.unwrap().len(), 1);
+
+        let _ = fs::remove_file(path);
+    }
+}
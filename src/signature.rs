@@ -0,0 +1,301 @@
+use crate::config::Dependency;
+use crate::config::Project;
+use crate::config::SignatureMode;
+use crate::install;
+use crate::util;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use tokio::fs;
+
+/* -------------------------------------------------------------------------------------------------
+Maven Central publishes a detached `.asc` signature for every artifact. We don't implement OpenPGP
+ourselves, instead we lean on `gpg` and a *local* keyring, the signatures are cached under `cache/`
+so once fetched, verification works completely offline e.g in CI.
+
+    verifySignatures: strict     # or "warn"
+    keyring: ~/.grind/keyring.gpg
+    trustedKeys:
+      com.fasterxml.jackson: ["8E04F3ABF2D31BB5A3A9A7A0F7C3C1A9D2D3B0E1"]
+
+A key listed for a groupId is also trusted for any "child" groupId e.g `com.fasterxml.jackson.core`
+------------------------------------------------------------------------------------------------- */
+
+const DEFAULT_KEYRING: &str = "~/.grind/keyring.gpg";
+
+pub async fn verify_all(project: &Project, deps: &[Dependency]) -> bool {
+    let mode = match project.verifySignatures {
+        Some(mode) => mode,
+        None => return true,
+    };
+
+    let keyring = project.keyring.as_deref().unwrap_or(DEFAULT_KEYRING);
+    let keyring = match util::expand_tilde(keyring) {
+        Some(path) => path.display().to_string(),
+        None => keyring.to_string(),
+    };
+    let trusted = project.trustedKeys.clone().unwrap_or_default();
+
+    println!("🔏 verifying signatures using keyring {}...", keyring);
+
+    let mut failures = 0;
+
+    for dep in deps {
+        match self::verify_dep(dep, &keyring, &trusted).await {
+            Ok(fingerprint) => println!(
+                "[OK] {}:{}:{} | {}",
+                dep.groupId, dep.artifactId, dep.version, fingerprint
+            ),
+            Err(e) => {
+                failures += 1;
+                let label = match mode {
+                    SignatureMode::Warn => "⚠️ [WARN]",
+                    SignatureMode::Strict => "❌ [FAIL]",
+                };
+                println!(
+                    "{} {}:{}:{} | {}",
+                    label, dep.groupId, dep.artifactId, dep.version, e
+                );
+            }
+        }
+    }
+
+    if failures == 0 {
+        println!("✅ All {} signature(s) verified.", deps.len());
+        return true;
+    }
+
+    match mode {
+        SignatureMode::Warn => {
+            println!("⚠️ {} artifact(s) failed signature verification", failures);
+            true
+        }
+        SignatureMode::Strict => {
+            println!(
+                "❌ {} artifact(s) failed signature verification (verifySignatures: strict)",
+                failures
+            );
+            false
+        }
+    }
+}
+
+async fn verify_dep(
+    dep: &Dependency,
+    keyring: &str,
+    trusted: &HashMap<String, Vec<String>>,
+) -> Result<String, String> {
+    let keys = self::get_trusted_keys(&dep.groupId, trusted)
+        .ok_or(format!("no trustedKeys configured for {}", dep.groupId))?;
+
+    let jar_path = install::get_downloaded_path(dep);
+    if !Path::new(&jar_path).exists() {
+        return Err(format!("missing jar {}", jar_path));
+    }
+
+    let asc_path = self::get_signature(dep).await?;
+
+    let output = Command::new("gpg")
+        .args(["--batch", "--no-default-keyring", "--keyring", keyring])
+        .args(["--status-fd", "1", "--verify", &asc_path, &jar_path])
+        .output()
+        .map_err(|e| format!("unable to run gpg: {}", e))?;
+
+    let status = String::from_utf8_lossy(&output.stdout);
+    let fingerprints = self::parse_valid_signatures(&status);
+
+    if fingerprints.is_empty() {
+        return Err("no valid signature (unknown key or bad signature)".to_string());
+    }
+
+    fingerprints
+        .into_iter()
+        .find(|fpr| keys.iter().any(|key| self::is_same_key(key, fpr)))
+        .ok_or("signed by a key that is not in trustedKeys".to_string())
+}
+
+fn get_signature_path(dep: &Dependency) -> String {
+    // named like the jar itself, a classifier jar has its own signature
+    format!(
+        "cache/{}_{}_{}{}.jar.asc",
+        dep.groupId,
+        dep.artifactId,
+        dep.version,
+        install::get_classifier_suffix(dep)
+    )
+}
+
+async fn get_signature(dep: &Dependency) -> Result<String, String> {
+    let local_path = self::get_signature_path(dep);
+
+    if Path::new(&local_path).exists() {
+        return Ok(local_path);
+    }
+
+    let url = format!("{}.asc", install::build_jar_url(dep));
+    println!("🌎 ==> fetching signature for {}", dep.artifactId);
+
+    let resp = reqwest::get(&url).await.map_err(|e| e.to_string())?;
+
+    if !resp.status().is_success() {
+        return Err(format!(
+            "unable to download signature, HTTP Status Code: {}",
+            resp.status()
+        ));
+    }
+
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+
+    fs::create_dir_all("cache")
+        .await
+        .map_err(|e| e.to_string())?;
+    fs::write(&local_path, body)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(local_path)
+}
+
+fn get_trusted_keys<'a>(
+    group_id: &str,
+    trusted: &'a HashMap<String, Vec<String>>,
+) -> Option<&'a Vec<String>> {
+    // the most specific groupId wins e.g `com.fasterxml.jackson.core` over `com.fasterxml`
+    trusted
+        .iter()
+        .filter(|(group, _)| {
+            group_id == group.as_str() || group_id.starts_with(&format!("{}.", group))
+        })
+        .max_by_key(|(group, _)| group.len())
+        .map(|(_, keys)| keys)
+}
+
+fn parse_valid_signatures(status: &str) -> Vec<String> {
+    /* ---------------------------------------------------------------------------------------------
+    gpg --status-fd output looks like:
+
+        [GNUPG:] VALIDSIG <fpr> <date> <timestamp> <expire> <ver> <res> <algo> <hash> <class> <primary-fpr>
+
+    we collect both the signing (sub)key fingerprint and the primary key fingerprint
+    --------------------------------------------------------------------------------------------- */
+    let mut fingerprints = Vec::new();
+
+    for line in status.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() >= 3 && fields[0] == "[GNUPG:]" && fields[1] == "VALIDSIG" {
+            fingerprints.push(fields[2].to_string());
+            if let Some(primary) = fields.get(11) {
+                fingerprints.push(primary.to_string());
+            }
+        }
+    }
+    fingerprints
+}
+
+pub fn normalize_key(key: &str) -> Option<String> {
+    // only full fingerprints (40 hex chars) and long key ids (16) are accepted, a short id (8)
+    // is trivially spoofed
+    let key = key.replace(' ', "").to_uppercase();
+    let key = key.trim_start_matches("0X");
+
+    let valid = (key.len() == 16 || key.len() == 40) && key.chars().all(|c| c.is_ascii_hexdigit());
+    valid.then(|| key.to_string())
+}
+
+fn is_same_key(trusted: &str, fingerprint: &str) -> bool {
+    let Some(trusted) = self::normalize_key(trusted) else {
+        return false;
+    };
+    let fingerprint = fingerprint.to_uppercase();
+
+    match trusted.len() {
+        // a long key id is the last 16 hex chars of the fingerprint
+        16 => fingerprint.len() == 40 && fingerprint.ends_with(&trusted),
+        _ => fingerprint == trusted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trusted_keys_and_status_parsing() {
+        let mut trusted = HashMap::new();
+        trusted.insert(
+            "com.fasterxml".to_string(),
+            vec!["AAAA BBBB CCCC DDDD".to_string()],
+        );
+        trusted.insert(
+            "com.fasterxml.jackson.core".to_string(),
+            vec!["0x28118C2A4B0F2E5F".to_string()],
+        );
+
+        let keys = get_trusted_keys("com.fasterxml.jackson.core", &trusted).unwrap();
+        assert_eq!(keys[0], "0x28118C2A4B0F2E5F");
+        assert!(get_trusted_keys("com.fasterxmlfoo", &trusted).is_none());
+
+        let status = "[GNUPG:] NEWSIG\n\
+            [GNUPG:] GOODSIG 28118C2A4B0F2E5F Tatu Saloranta <tatu@fasterxml.com>\n\
+            [GNUPG:] VALIDSIG 1DB5D1C1C4F2C7B07C65C6A128118C2A4B0F2E5F 2024-07-05 1720195315 0 4 0 1 10 00 1DB5D1C1C4F2C7B07C65C6A128118C2A4B0F2E5F\n";
+
+        let fingerprints = parse_valid_signatures(status);
+        assert_eq!(fingerprints.len(), 2);
+        assert!(is_same_key(&keys[0], &fingerprints[0]));
+        assert!(!is_same_key("AAAABBBBCCCCDDDD", &fingerprints[0]));
+        assert!(is_same_key(
+            "1DB5 D1C1 C4F2 C7B0 7C65 C6A1 2811 8C2A 4B0F 2E5F",
+            &fingerprints[0]
+        ));
+    }
+
+    #[test]
+    fn test_signature_per_classifier() {
+        let lwjgl = |classifier: Option<&str>| Dependency {
+            groupId: "org.lwjgl".to_string(),
+            artifactId: "lwjgl".to_string(),
+            version: "3.3.4".to_string(),
+            classifier: classifier.map(str::to_string),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            get_signature_path(&lwjgl(None)),
+            "cache/org.lwjgl_lwjgl_3.3.4.jar.asc"
+        );
+        assert_eq!(
+            get_signature_path(&lwjgl(Some("natives-linux"))),
+            "cache/org.lwjgl_lwjgl_3.3.4-natives-linux.jar.asc"
+        );
+        assert_ne!(
+            get_signature_path(&lwjgl(Some("natives-linux"))),
+            get_signature_path(&lwjgl(Some("natives-macos")))
+        );
+    }
+
+    #[test]
+    fn test_short_and_malformed_keys() {
+        let fingerprint = "1DB5D1C1C4F2C7B07C65C6A128118C2A4B0F2E5F";
+
+        // short key ids and arbitrary suffixes are rejected
+        assert!(!is_same_key("4B0F2E5F", fingerprint));
+        assert!(!is_same_key("0x4B0F2E5F", fingerprint));
+        assert!(!is_same_key("2E5F", fingerprint));
+        assert!(!is_same_key("F", fingerprint));
+        assert!(!is_same_key("", fingerprint));
+
+        // right length, but not hex
+        assert!(!is_same_key("28118C2A4B0F2EZZ", fingerprint));
+        assert!(normalize_key("0x28118C2A4B0F2EZZ").is_none());
+
+        // a 40 char key has to be the whole fingerprint
+        assert!(!is_same_key(
+            "0000D1C1C4F2C7B07C65C6A128118C2A4B0F2E5F",
+            fingerprint
+        ));
+        assert_eq!(
+            normalize_key("0x2811 8c2a 4b0f 2e5f").as_deref(),
+            Some("28118C2A4B0F2E5F")
+        );
+    }
+}
@@ -1,14 +1,17 @@
 use crate::Grind;
+use crate::error::GrindError;
+use crate::java;
+use crate::util;
 use crate::util::shell_stream;
 
-pub fn execute_task(grind: Grind, task: String) {
+pub fn execute_task(grind: Grind, task: String) -> Result<(), GrindError> {
     if task.to_lowercase() == "list" {
         println!("available tasks:\n");
         for k in grind.project.tasks.keys() {
             println!(" - {} ", k);
         }
         println!();
-        return;
+        return Ok(());
     }
 
     let target = grind
@@ -19,12 +22,21 @@ pub fn execute_task(grind: Grind, task: String) {
 
     if let Some((k, v)) = target {
         println!("==> executing task [{}]", k);
-        let _ = shell_stream(v);
+        // tasks are user defined shell snippets, so this is the one place bash is still needed
+        let status = shell_stream(v, &java::get_task_envs())?;
+        if !status.success() {
+            return Err(GrindError::Process(
+                format!("❌ task [{}] failed", k),
+                util::get_exit_code(&status),
+            ));
+        }
+        Ok(())
     } else {
         println!("unknown task '{}', available tasks:\n", task);
         for k in grind.project.tasks.keys() {
             println!(" - {} ", k);
         }
         println!();
+        Err(GrindError::Config(format!("❌ unknown task '{}'", task)))
     }
 }
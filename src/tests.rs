@@ -2,32 +2,47 @@ use std::path::Path;
 
 use crate::BuildTarget;
 use crate::Grind;
+use crate::RunArgs;
 use crate::build;
+use crate::error::GrindError;
 use crate::handle_validate_integrity;
+use crate::java;
 use crate::util;
-use crate::util::shell;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use tokio::task;
 
-pub async fn run_tests(grind: Grind, tests: Vec<String>) {
+pub async fn run_tests(grind: Grind, tests: Vec<String>) -> Result<(), GrindError> {
     if !self::check_plugin_exists() {
-        let _ = self::download_test_plugin().await;
+        self::download_test_plugin().await.map_err(|e| {
+            GrindError::Resolution(format!("❌ unable to download the TestTube plugin: {}", e))
+        })?;
     }
 
-    if self::check_plugin_integrity() {
-        // TODO: at the moment we're NOT passing any compiler flags
-        build::execute_build(&grind, BuildTarget::IncludeTest, String::new());
-        let args = tests.join(" ");
-        let cmd = format!(
-            "java -cp \"target:target/test:libs/*:plugins/TestTube/libs/*:plugins/TestTube/TestTube.jar\" org.grind.TestTube {}",
-            args
-        );
-        let out = shell(&cmd);
-        println!("{}", out);
-    } else {
-        println!("❌ the TestTube plugin is corrupted, try deleting the `TestTube/` folder");
+    if !self::check_plugin_integrity() {
+        return Err(GrindError::Integrity(
+            "❌ the TestTube plugin is corrupted, try deleting the `TestTube/` folder".to_string(),
+        ));
+    }
+
+    // the `java` options (release, encoding, lint) and annotation processors still apply, but no
+    // profile is used for tests
+    build::execute_build(&grind, BuildTarget::IncludeTest, &RunArgs::default())?;
+
+    let status = util::exec(
+        java::command("java")
+            .args([
+                "-cp",
+                "target:target/test:libs/*:libs-test/*:plugins/TestTube/libs/*:plugins/TestTube/TestTube.jar",
+                "org.grind.TestTube",
+            ])
+            .args(&tests),
+    )?;
+
+    if !status.success() {
+        return Err(GrindError::Tests(format!("❌ tests failed: {}", status)));
     }
+    Ok(())
 }
 
 fn check_plugin_exists() -> bool {
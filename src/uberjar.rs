@@ -1,3 +1,5 @@
+use crate::incremental;
+use crate::java;
 use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::{self, Read, Write};
@@ -10,7 +12,11 @@ pub struct FatJarConfig<'a> {
     pub output_jar: &'a Path,
     pub classes_dir: &'a Path,
     pub libs_dir: &'a Path,
+    // e.g `provided` jars, expected to already be on the runtime classpath
+    pub excluded_jars: &'a [String],
     pub main_class: &'a str,
+    // extra manifest attributes, each on its own line e.g the `java.release`
+    pub attributes: &'a str,
     pub group_id: &'a str,
     pub artifact_id: &'a str,
 }
@@ -51,6 +57,12 @@ pub fn build_fat_jar(config: &FatJarConfig) -> io::Result<()> {
         .filter_map(Result::ok)
         .map(|e| e.path())
         .filter(|p| p.extension().map(|e| e == "jar").unwrap_or(false))
+        .filter(|p| {
+            !config
+                .excluded_jars
+                .iter()
+                .any(|excluded| Path::new(excluded) == p.as_path())
+        })
         .collect();
 
     let total = jars.len();
@@ -112,12 +124,15 @@ fn generate_manifest(config: &FatJarConfig) -> String {
          Implementation-Vendor-Id: {group}\n\
          Built-By: {user}\n\
          Build-Jdk: {jdk}\n\
-         Implementation-Version: 1.0.0\n\n",
+         Implementation-Version: 1.0.0{attributes}\n\n",
         main = config.main_class,
         artifact = config.artifact_id,
         group = config.group_id,
         user = "grind",
-        jdk = std::env::var("JAVA_HOME").unwrap_or_else(|_| "unknown".into()),
+        jdk = java::get_java_home()
+            .map(|home| home.display().to_string())
+            .unwrap_or_else(|| "unknown".into()),
+        attributes = config.attributes,
     )
 }
 
@@ -142,6 +157,11 @@ fn add_directory_classes(
                 .to_string_lossy()
                 .replace('\\', "/");
 
+            // the annotation processors' output, these are sources not classes
+            if entry.path().starts_with(incremental::GENERATED_DIR) {
+                continue;
+            }
+
             if seen.insert(rel_path.clone()) {
                 writer.start_file(rel_path.clone(), options)?;
                 let mut f = File::open(entry.path())?;
@@ -1,4 +1,6 @@
 use crate::Grind;
+use crate::error::GrindError;
+use crate::validate;
 use flate2::read::GzDecoder;
 use std::cmp::Ordering;
 use std::collections::HashSet;
@@ -6,42 +8,22 @@ use std::env;
 use std::fs;
 use std::fs::File;
 use std::io;
-use std::io::{BufRead, BufReader};
 use std::os::unix::fs::symlink;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Command, ExitStatus};
 use tar::Archive;
 use zip::ZipArchive;
 
-pub fn parse_grind_file() -> Option<Grind> {
-    let grind_raw = fs::read_to_string("grind.yml").unwrap();
-    let parsed: Grind = serde_yaml::from_str(&grind_raw).unwrap();
-    Some(parsed)
-}
-
-pub fn shell(cmd: &str) -> String {
-    // print!("[DEBUG-CMD] {}", cmd);
-    let output = Command::new("bash")
-        .arg("-c")
-        .arg(cmd)
-        .output()
-        .expect("failed to execute command");
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
-    let mut result = String::new();
+pub fn parse_grind_file() -> Result<Grind, GrindError> {
+    let (grind, issues) = validate::load_grind_file().map_err(GrindError::Config)?;
 
-    if !stdout.trim().is_empty() {
-        result.push_str(&stdout);
+    validate::print_issues(&issues);
+    if validate::has_errors(&issues) {
+        return Err(GrindError::Config(
+            "❌ invalid grind.yml, see `grind check-config` for details".to_string(),
+        ));
     }
-
-    if !stderr.trim().is_empty() {
-        result.push_str("\n⚠️ Error:\n");
-        result.push_str(&stderr);
-    }
-
-    result.trim().to_string()
+    Ok(grind)
 }
 
 pub enum GrindPath {
@@ -49,7 +31,8 @@ pub enum GrindPath {
     Exlude,
 }
 
-pub fn shell_custom_path(cmd: &str, grind_path_option: GrindPath) -> String {
+pub fn get_custom_path(grind_path_option: GrindPath) -> String {
+    // the PATH with the grind managed JDK either first, or left out completely
     let path = env::var("PATH").unwrap_or_default();
     let home = env::var("HOME").unwrap_or_default();
     let mut paths: Vec<&str> = path.split(':').collect();
@@ -63,30 +46,7 @@ pub fn shell_custom_path(cmd: &str, grind_path_option: GrindPath) -> String {
             paths.retain(|p| !p.contains("grind/jdks/current"));
         }
     }
-    let new_path = paths.join(":");
-
-    let output = Command::new("bash")
-        .env("PATH", &new_path)
-        .arg("-lc")
-        .arg(cmd)
-        .output()
-        .expect("failed to execute command");
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
-    let mut result = String::new();
-
-    if !stdout.trim().is_empty() {
-        result.push_str(&stdout);
-    }
-
-    if !stderr.trim().is_empty() {
-        result.push_str("\n⚠️ Error:\n");
-        result.push_str(&stderr);
-    }
-
-    result.trim().to_string()
+    paths.join(":")
 }
 
 #[allow(dead_code)]
@@ -111,37 +71,133 @@ pub fn shell_result(cmd: &str) -> Result<String, String> {
     Err("Error: Unable both stdout and stderror failed..".to_string())
 }
 
-pub fn shell_stream(cmd: &str) -> std::io::Result<()> {
-    let mut child = Command::new("bash")
+pub fn shell_stream(cmd: &str, envs: &[(String, String)]) -> Result<ExitStatus, String> {
+    // ONLY for user defined tasks, everything else goes through `self::exec`
+    let status = Command::new("bash")
         .arg("-c")
         .arg(cmd)
-        .arg("--color=auto")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
+        .envs(envs.iter().cloned())
+        .status()
+        .map_err(|e| format!("❌ unable to run bash: {}", e))?;
 
-    let stdout = child.stdout.take().expect("Failed to capture stdout");
-    let stderr = child.stderr.take().expect("Failed to capture stderr");
+    println!("Process exited with: {}", status);
+    Ok(status)
+}
 
-    let stdout_reader = BufReader::new(stdout);
-    let stderr_reader = BufReader::new(stderr);
+pub fn exec(command: &mut Command) -> Result<ExitStatus, String> {
+    /* ---------------------------------------------------------------------------------------------
+    runs a program with an explicit argument vector (no shell, so no globbing or quoting issues),
+    the child shares our stdout/stderr so its output stays interleaved exactly as it was written.
+    --------------------------------------------------------------------------------------------- */
+    let program = command.get_program().to_string_lossy().to_string();
+    command
+        .status()
+        .map_err(|e| format!("❌ unable to run {}: {}", program, e))
+}
 
-    // Handle stderr in a background thread
-    std::thread::spawn(move || {
-        for line in stderr_reader.lines().flatten() {
-            eprintln!("[stderr] {}", line);
-        }
-    });
+pub fn exec_output(command: &mut Command) -> Result<String, String> {
+    // like `exec`, but for the few places that need to read what the program printed
+    let program = command.get_program().to_string_lossy().to_string();
+    let output = command
+        .output()
+        .map_err(|e| format!("❌ unable to run {}: {}", program, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "❌ {} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn get_exit_code(status: &ExitStatus) -> i32 {
+    // a process killed by a signal has no exit code, but it certainly didn't succeed
+    status.code().filter(|code| *code != 0).unwrap_or(1)
+}
 
-    // Handle stdout on the main thread
-    for line in stdout_reader.lines().flatten() {
-        println!("{}", line);
+pub fn write_argfile(path: &str, args: &[String]) -> Result<(), String> {
+    // see "Command-Line Argument Files" in the javac docs, quoted so paths can contain spaces
+    let quoted: Vec<String> = args
+        .iter()
+        .map(|arg| format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
     }
+    fs::write(path, quoted.join("\n")).map_err(|e| format!("{}: {}", path, e))
+}
 
-    let status = child.wait()?;
-    println!("Process exited with: {}", status);
+pub fn split_args(input: &str) -> Vec<String> {
+    // whitespace separated, with "double" or 'single' quotes keeping an argument together, a
+    // backslash escapes a quote, a space or itself (anywhere but inside 'single' quotes)
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_arg = false;
+    let mut escaped = false;
+
+    for c in input.chars() {
+        if escaped {
+            escaped = false;
+            let escapable =
+                matches!(c, '"' | '\\' | '\'') || (quote.is_none() && c.is_whitespace());
+            if !escapable {
+                current.push('\\');
+            }
+            current.push(c);
+            in_arg = true;
+            continue;
+        }
 
-    Ok(())
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), c) => current.push(c),
+            (_, '\\') => {
+                escaped = true;
+                in_arg = true;
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    // a trailing backslash has nothing to escape, so it's kept as is
+    if escaped {
+        current.push('\\');
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+pub fn list_files(dir: &str, extension: &str) -> Vec<String> {
+    // recursive, unlike `ls_with_ext`, sorted so the results are stable
+    let mut files: Vec<String> = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == extension))
+        .map(|entry| entry.path().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    files
 }
 
 pub fn ls_with_ext(dir: &str, extension: &str) -> std::io::Result<Vec<String>> {
@@ -388,4 +444,53 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_split_args() {
+        let cases: Vec<(&str, Vec<&str>)> = vec![
+            ("-Xlint:all  -g", vec!["-Xlint:all", "-g"]),
+            (
+                r#"-Dgreeting="hello world" -d 'out dir'"#,
+                vec!["-Dgreeting=hello world", "-d", "out dir"],
+            ),
+            (
+                r#"-Dquote=\"x\" "a \"b\"""#,
+                vec![r#"-Dquote="x""#, r#"a "b""#],
+            ),
+            (r#"'it\'s'"#, vec![r#"it\s"#]),
+            (
+                r#"/opt/my\ libs/a.jar C:\tools"#,
+                vec!["/opt/my libs/a.jar", r#"C:\tools"#],
+            ),
+            (r#"-Dempty="" end\"#, vec!["-Dempty=", r#"end\"#]),
+            ("", vec![]),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(split_args(input), expected, "split_args({:?})", input);
+        }
+    }
+
+    #[test]
+    fn test_write_argfile() {
+        let dir = std::env::temp_dir().join(format!("grind-argfile-{}", std::process::id()));
+        let path = dir.join("nested").join("sources.txt");
+        let path = path.to_string_lossy().to_string();
+
+        let args = vec![
+            "src/main/java/My App/Main.java".to_string(),
+            r#"-Dquote="x""#.to_string(),
+            r#"C:\tools\lib.jar"#.to_string(),
+        ];
+        write_argfile(&path, &args).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "\"src/main/java/My App/Main.java\"\n\"-Dquote=\\\"x\\\"\"\n\"C:\\\\tools\\\\lib.jar\""
+        );
+        // javac reads it back the same way `split_args` does
+        assert_eq!(split_args(&fs::read_to_string(&path).unwrap()), args);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,461 @@
+use crate::Grind;
+use crate::config::ProjectType;
+use crate::error::GrindError;
+use crate::signature;
+use crate::validate_artifact_id;
+use crate::validate_namespace;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/* -------------------------------------------------------------------------------------------------
+Loads and validates the `grind.yml`, in three passes:
+
+    1. YAML/structure errors, reported with the line and column e.g a missing `version`
+    2. unknown fields, most likely a typo e.g `dependecies:`, only a warning as it's ignored
+    3. semantic checks that serde can't express e.g duplicate dependencies or an unknown scope
+
+`grind check-config` reports everything, every other command refuses to run on any errors.
+------------------------------------------------------------------------------------------------- */
+
+const GRIND_FILE: &str = "grind.yml";
+const SCOPES: [&str; 4] = ["compile", "runtime", "provided", "test"];
+
+#[derive(Debug, PartialEq)]
+pub enum Level {
+    Warning,
+    Error,
+}
+
+#[derive(Debug)]
+pub struct Issue {
+    pub level: Level,
+    pub message: String,
+}
+
+impl Issue {
+    fn warning(message: String) -> Self {
+        Issue {
+            level: Level::Warning,
+            message,
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Issue {
+            level: Level::Error,
+            message,
+        }
+    }
+}
+
+pub fn load_grind_file() -> Result<(Grind, Vec<Issue>), String> {
+    if !Path::new(GRIND_FILE).exists() {
+        return Err(format!(
+            "⚠️ Error: no {} found in the current directory, to create a new project use `grind new <groupId>/<artifactId>`",
+            GRIND_FILE
+        ));
+    }
+
+    let raw = fs::read_to_string(GRIND_FILE)
+        .map_err(|e| format!("⚠️ Error: unable to read {}: {}", GRIND_FILE, e))?;
+
+    let (grind, unknown) = self::parse(&raw)?;
+
+    let mut issues: Vec<Issue> = unknown
+        .into_iter()
+        .map(|field| Issue::warning(format!("unknown field `{}` is ignored", field)))
+        .collect();
+    issues.extend(self::check(&grind));
+
+    Ok((grind, issues))
+}
+
+pub fn parse(raw: &str) -> Result<(Grind, Vec<String>), String> {
+    let mut unknown = Vec::new();
+
+    let mut track = |path: serde_ignored::Path| unknown.push(path.to_string());
+
+    let deserializer = serde_yaml::Deserializer::from_str(raw);
+    let ignored = serde_ignored::Deserializer::new(deserializer, &mut track);
+
+    match serde_path_to_error::deserialize::<_, Grind>(ignored) {
+        Ok(grind) => Ok((grind, unknown)),
+        Err(e) => Err(self::format_error(raw, &e)),
+    }
+}
+
+fn format_error(raw: &str, e: &serde_path_to_error::Error<serde_yaml::Error>) -> String {
+    let path = e.path().to_string();
+    let inner = e.inner();
+
+    // serde_yaml tacks the location onto the message, we show it up front instead
+    let mut message = inner.to_string();
+    if let Some(index) = message.rfind(" at line ") {
+        message.truncate(index);
+    }
+    if path != "." && !message.starts_with(&path) {
+        message = format!("{}: {}", path, message);
+    }
+
+    match inner.location() {
+        Some(location) => {
+            let mut error = format!(
+                "❌ invalid {}:{}:{} {}",
+                GRIND_FILE,
+                location.line(),
+                location.column(),
+                message
+            );
+            if let Some(line) = raw.lines().nth(location.line().saturating_sub(1)) {
+                let gutter = location.line().to_string().len();
+                error.push_str(&format!(
+                    "\n {} | {}\n {} | {}^",
+                    location.line(),
+                    line,
+                    " ".repeat(gutter),
+                    " ".repeat(location.column().saturating_sub(1))
+                ));
+            }
+            error
+        }
+        None => format!("❌ invalid {}: {}", GRIND_FILE, message),
+    }
+}
+
+pub fn check(grind: &Grind) -> Vec<Issue> {
+    let project = &grind.project;
+    let mut issues = Vec::new();
+
+    // the messages are shared with `grind new`, which already prefix them with a ⚠️
+    if let Err(e) = validate_namespace(&project.groupId) {
+        let e = e.trim_start_matches("⚠️ ");
+        issues.push(Issue::error(format!(
+            "groupId '{}': {}",
+            project.groupId, e
+        )));
+    }
+    if let Err(e) = validate_artifact_id(&project.artifactId) {
+        let e = e.trim_start_matches("⚠️ ");
+        issues.push(Issue::error(format!(
+            "artifactId '{}': {}",
+            project.artifactId, e
+        )));
+    }
+
+    if project.sourcesJar == Some(true) && project.r#type != Some(ProjectType::Library) {
+        issues.push(Issue::warning(
+            "sourcesJar is only used when `type: library`".to_string(),
+        ));
+    }
+
+    if let Some(jdk) = project.jdk
+        && jdk < 8
+    {
+        issues.push(Issue::error(format!(
+            "jdk {} is not available, the oldest is 8",
+            jdk
+        )));
+    }
+    if let (Some(jdk), Some(release)) = (
+        project.jdk,
+        project.java.as_ref().and_then(|java| java.release),
+    ) && release > jdk
+    {
+        issues.push(Issue::error(format!(
+            "java.release {} needs JDK {} or newer, but the project pins JDK {}",
+            release, release, jdk
+        )));
+    }
+
+    if let Some(java) = &project.java {
+        if let Some(release) = java.release
+            && release < 8
+        {
+            issues.push(Issue::warning(format!(
+                "java.release {} is not supported by any current JDK, the oldest is 8",
+                release
+            )));
+        }
+        if java.encoding.as_ref().is_some_and(|e| e.trim().is_empty()) {
+            issues.push(Issue::error("java: `encoding` is empty".to_string()));
+        }
+        if java.lint.iter().flatten().any(|l| l.trim().is_empty()) {
+            issues.push(Issue::error("java: `lint` has an empty option".to_string()));
+        }
+    }
+
+    // a class name is checked the same way as a namespace e.g com.example.Main
+    if let Some(main_class) = &project.mainClass
+        && validate_namespace(main_class).is_err()
+    {
+        issues.push(Issue::error(format!(
+            "mainClass '{}' is not a valid fully qualified class name",
+            main_class
+        )));
+    }
+    for (name, class) in project.entrypoints.iter().flatten() {
+        if name.trim().is_empty() || name.contains(char::is_whitespace) {
+            issues.push(Issue::error(format!(
+                "entrypoints: '{}' is not a valid entrypoint name",
+                name
+            )));
+        }
+        if validate_namespace(class).is_err() {
+            issues.push(Issue::error(format!(
+                "entrypoints.{}: '{}' is not a valid fully qualified class name",
+                name, class
+            )));
+        }
+    }
+
+    let mut seen = HashSet::new();
+
+    for (i, dep) in project.dependencies.iter().enumerate() {
+        let at = format!("dependencies[{}]", i);
+
+        for (field, value) in [
+            ("groupId", &dep.groupId),
+            ("artifactId", &dep.artifactId),
+            ("version", &dep.version),
+        ] {
+            if value.trim().is_empty() {
+                issues.push(Issue::error(format!("{}: `{}` is empty", at, field)));
+            }
+        }
+
+        if let Some(scope) = &dep.scope
+            && !SCOPES.contains(&scope.as_str())
+        {
+            issues.push(Issue::error(format!(
+                "{}: unknown scope '{}' for {}:{}, expected one of: {}",
+                at,
+                scope,
+                dep.groupId,
+                dep.artifactId,
+                SCOPES.join(", ")
+            )));
+        }
+
+        if !seen.insert((&dep.groupId, &dep.artifactId, &dep.classifier)) {
+            issues.push(Issue::error(format!(
+                "{}: {}:{} is declared more than once",
+                at, dep.groupId, dep.artifactId
+            )));
+        }
+
+        for exclusion in dep.exclusions.iter().flatten() {
+            if exclusion.is_empty() || exclusion.split(':').count() > 2 {
+                issues.push(Issue::warning(format!(
+                    "{}: exclusion '{}' should be <groupId>[:<artifactId>]",
+                    at, exclusion
+                )));
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for (i, processor) in project.annotationProcessors.iter().flatten().enumerate() {
+        let at = format!("annotationProcessors[{}]", i);
+
+        for (field, value) in [
+            ("groupId", &processor.groupId),
+            ("artifactId", &processor.artifactId),
+            ("version", &processor.version),
+        ] {
+            if value.trim().is_empty() {
+                issues.push(Issue::error(format!("{}: `{}` is empty", at, field)));
+            }
+        }
+
+        // processors are never on the classpath, so a scope means nothing
+        if processor.scope.is_some() {
+            issues.push(Issue::warning(format!(
+                "{}: `scope` is ignored for annotation processors",
+                at
+            )));
+        }
+
+        if !seen.insert((&processor.groupId, &processor.artifactId)) {
+            issues.push(Issue::error(format!(
+                "{}: {}:{} is declared more than once",
+                at, processor.groupId, processor.artifactId
+            )));
+        }
+    }
+    if project
+        .processorOptions
+        .iter()
+        .flatten()
+        .any(|(key, _)| key.trim().is_empty() || key.contains(char::is_whitespace))
+    {
+        issues.push(Issue::error(
+            "processorOptions: option names can't be empty or contain spaces".to_string(),
+        ));
+    }
+    if project.processorOptions.is_some() && project.annotationProcessors.is_none() {
+        issues.push(Issue::warning(
+            "processorOptions are only used along with `annotationProcessors`".to_string(),
+        ));
+    }
+
+    if let Some(default) = &project.defaultProfile
+        && !project
+            .profiles
+            .as_ref()
+            .is_some_and(|profiles| profiles.contains_key(default))
+    {
+        issues.push(Issue::error(format!(
+            "defaultProfile '{}' is not one of the `profiles`",
+            default
+        )));
+    }
+    for (name, profile) in project.profiles.iter().flatten() {
+        for dir in profile.resources.iter().flatten() {
+            if dir.trim().is_empty() {
+                issues.push(Issue::error(format!(
+                    "profiles.{}: a resources folder is empty",
+                    name
+                )));
+            } else if !Path::new(dir).is_dir() {
+                issues.push(Issue::warning(format!(
+                    "profiles.{}: resources folder '{}' not found",
+                    name, dir
+                )));
+            }
+        }
+    }
+
+    if project
+        .filterResources
+        .iter()
+        .flatten()
+        .any(|glob| glob.trim().is_empty())
+    {
+        issues.push(Issue::error("filterResources: a glob is empty".to_string()));
+    }
+    // e.g `project.version` is always the project's own
+    for key in project.properties.iter().flatten().map(|(key, _)| key) {
+        if key.starts_with("project.") || key.starts_with("env.") {
+            issues.push(Issue::warning(format!(
+                "properties: '{}' clashes with the built in `project.` and `env.` placeholders",
+                key
+            )));
+        }
+    }
+    if project.properties.is_some() && project.filterResources.is_none() {
+        issues.push(Issue::warning(
+            "properties are only used along with `filterResources`".to_string(),
+        ));
+    }
+
+    for (group, keys) in project.trustedKeys.iter().flatten() {
+        for key in keys
+            .iter()
+            .filter(|key| signature::normalize_key(key).is_none())
+        {
+            issues.push(Issue::error(format!(
+                "trustedKeys.{}: '{}' should be a full fingerprint (40 hex chars) or a long key id (16)",
+                group, key
+            )));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for (i, constraint) in project.constraints.iter().flatten().enumerate() {
+        if !seen.insert((&constraint.groupId, &constraint.artifactId)) {
+            issues.push(Issue::error(format!(
+                "constraints[{}]: {}:{} is constrained more than once",
+                i, constraint.groupId, constraint.artifactId
+            )));
+        }
+    }
+
+    issues
+}
+
+pub fn print_issues(issues: &[Issue]) {
+    for issue in issues {
+        match issue.level {
+            Level::Warning => println!("⚠️ {}: {}", GRIND_FILE, issue.message),
+            Level::Error => println!("❌ {}: {}", GRIND_FILE, issue.message),
+        }
+    }
+}
+
+pub fn has_errors(issues: &[Issue]) -> bool {
+    issues.iter().any(|issue| issue.level == Level::Error)
+}
+
+pub fn execute_check_config() -> Result<(), GrindError> {
+    let (_, issues) = self::load_grind_file().map_err(GrindError::Config)?;
+
+    self::print_issues(&issues);
+
+    let errors = issues.iter().filter(|i| i.level == Level::Error).count();
+    let warnings = issues.len() - errors;
+
+    if errors > 0 {
+        return Err(GrindError::Config(format!(
+            "❌ {} error(s), {} warning(s)",
+            errors, warnings
+        )));
+    }
+    if warnings > 0 {
+        println!("✅ {} is valid, with {} warning(s)", GRIND_FILE, warnings);
+    } else {
+        println!("✅ {} is valid", GRIND_FILE);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_check() {
+        let raw = r#"project:
+  groupId: "com.example"
+  artifactId: "PaymentsApi"
+  version: "1.0.0"
+  name: "PaymentsApi"
+  description: "Update me!"
+  dependecies: []
+  dependencies:
+    - groupId: "junit"
+      artifactId: "junit"
+      version: "4.13.2"
+      scope: "tests"
+    - groupId: "junit"
+      artifactId: "junit"
+      version: "4.13.1"
+  tasks: {}
+"#;
+        let (grind, unknown) = parse(raw).unwrap();
+        assert_eq!(unknown, vec!["project.dependecies".to_string()]);
+
+        let issues = check(&grind);
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].message.contains("unknown scope 'tests'"));
+        assert!(issues[1].message.contains("declared more than once"));
+
+        let keys = raw.replace(
+            "  tasks: {}\n",
+            "  tasks: {}\n  trustedKeys:\n    junit: [\"0x4B0F2E5F\", \"1DB5D1C1C4F2C7B07C65C6A128118C2A4B0F2E5F\"]\n",
+        );
+        let (grind, _) = parse(&keys).unwrap();
+        let issues = check(&grind);
+        assert_eq!(issues.len(), 3);
+        assert!(
+            issues[2]
+                .message
+                .contains("trustedKeys.junit: '0x4B0F2E5F'")
+        );
+
+        let missing = raw.replace("      version: \"4.13.1\"\n", "");
+        let error = parse(&missing).unwrap_err();
+        assert!(error.contains("grind.yml:13:7"), "{}", error);
+        assert!(error.contains("missing field `version`"), "{}", error);
+    }
+}